@@ -1,15 +1,210 @@
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
 
-use nalgebra::{ComplexField, SMatrix, Scalar, SimdValue};
+use nalgebra::{ComplexField, RealField, SMatrix, Scalar, SimdValue};
 
-struct VecMat<const N: usize, F: Scalar + SimdValue + ComplexField + Copy> {
+use crate::kalman_fixed_gain::FixedGainEstimator;
+use crate::linalg;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct VecMat<const N: usize, F: Scalar + SimdValue + ComplexField + Copy> {
+    pub(crate) x: SMatrix<F, N, 1>,
+    pub(crate) P: SMatrix<F, N, N>,
+}
+
+/// Errors reported by the Kalman filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KalmanError {
+    /// The measurement noise covariance `R` is not symmetric positive-definite.
+    NonPositiveDefiniteNoise,
+
+    /// The innovation covariance `S` is too ill-conditioned to be reliably inverted.
+    IllConditionedInnovation,
+
+    /// The model propagation matrix `A` is not invertible.
+    SingularTransition,
+
+    /// The initial covariance `P_init` is not symmetric positive-definite, or exceeds the scale bounds.
+    InvalidInitialCovariance,
+
+    /// The innovation covariance `S` is singular, such as for noiseless redundant measurements.
+    SingularInnovation,
+
+    /// The state covariance `P` is not positive-definite, such as when generating sigma points.
+    NonPositiveDefiniteCovariance,
+
+    /// The dimensions of the model, measurement or input do not match the state dimension.
+    DimensionMismatch,
+
+    /// The time step is not positive and finite.
+    InvalidTimeStep,
+
+    /// No continuous-time model is set to discretize or integrate.
+    NoContinuousModel,
+}
+
+/// Prewhiten a measurement model using the Cholesky factor `L` of a dense `R = L*L^T`.
+/// Returns the whitened output matrix `L^-1 * C` and measurement `L^-1 * y`, which can be
+/// used in `.update` together with an identity covariance matrix.
+/// Returns `None` if `R` is not positive-definite.
+pub fn prewhiten<const Nx: usize, const Ny: usize, F: Scalar + SimdValue + ComplexField + Copy>(
+    C: &SMatrix<F, Ny, Nx>, // Output matrix
+    R: &SMatrix<F, Ny, Ny>, // Covariance
+    y: &SMatrix<F, Ny, 1>,  // Measurement
+) -> Option<(SMatrix<F, Ny, Nx>, SMatrix<F, Ny, 1>)> {
+    let L = R.cholesky()?.unpack();
+    Some((L.solve_lower_triangular(C)?, L.solve_lower_triangular(y)?))
+}
+
+/// Project a matrix onto the nearest valid covariance matrix, by symmetrizing it and clamping
+/// its eigenvalues to at least `min_eigenvalue`. Valid covariance matrices are left unchanged.
+pub fn nearest_covariance<const N: usize, F: RealField + Copy>(P: &SMatrix<F, N, N>, min_eigenvalue: F) -> SMatrix<F, N, N> {
+    let (eigenvalues, V) = linalg::symmetric_eigen(P);
+    let clamped = eigenvalues.map(|e| e.max(min_eigenvalue));
+    let P = V * SMatrix::from_diagonal(&clamped) * V.transpose();
+    (P + P.transpose()).scale(nalgebra::convert(0.5))
+}
+
+/// Clamp `x` component-wise into the box `(min, max)`, if any.
+fn clamp<const N: usize, F: RealField + Copy>(
     x: SMatrix<F, N, 1>,
-    P: SMatrix<F, N, N>,
+    bounds: Option<&(SMatrix<F, N, 1>, SMatrix<F, N, 1>)>,
+) -> SMatrix<F, N, 1> {
+    match bounds {
+        Some((min, max)) => SMatrix::from_fn(|i, _| x[i].max(min[i]).min(max[i])),
+        None => x,
+    }
+}
+
+/// Strip the rows of a measurement model where `mask` is `false`, by replacing them with
+/// uninformative rows with zero output, zero residual and unit variance uncorrelated with
+/// the remaining rows.
+fn strip_rows<const Nx: usize, const Ny: usize, F: RealField + Copy>(
+    C: &SMatrix<F, Ny, Nx>,
+    R: &SMatrix<F, Ny, Ny>,
+    y: &SMatrix<F, Ny, 1>,
+    mask: &[bool; Ny],
+) -> (SMatrix<F, Ny, Nx>, SMatrix<F, Ny, Ny>, SMatrix<F, Ny, 1>) {
+    let (mut C, mut R, mut y) = (*C, *R, *y);
+    for (i, &keep) in mask.iter().enumerate() {
+        if !keep {
+            C.row_mut(i).fill(F::zero());
+            R.row_mut(i).fill(F::zero());
+            R.column_mut(i).fill(F::zero());
+            R[(i, i)] = F::one();
+            y[i] = F::zero();
+        }
+    }
+    (C, R, y)
+}
+
+/// Compose two sequential linear models, first `x' = A1*x + B1*u` followed by
+/// `x'' = A2*x' + B2*u`, into a single transition `x'' = A*x + B*u` with the same input `u`.
+/// Returns the composed `(A, B) = (A2*A1, A2*B1 + B2)`. Note that the process noise of the
+/// composed model should correspondingly be `A2*Q1*A2^T + Q2`.
+pub fn compose_models<const Nx: usize, const Nu: usize, F: Scalar + SimdValue + ComplexField + Copy>(
+    A1: &SMatrix<F, Nx, Nx>,
+    B1: &SMatrix<F, Nx, Nu>,
+    A2: &SMatrix<F, Nx, Nx>,
+    B2: &SMatrix<F, Nx, Nu>,
+) -> (SMatrix<F, Nx, Nx>, SMatrix<F, Nx, Nu>) {
+    (A2 * A1, A2 * B1 + B2)
+}
+
+/// Discretize the continuous-time model `x_dot = F*x + w`, with white process noise `w` of
+/// power spectral density `Q_cont`, over the time step `dt` using Van Loan's method.
+/// Returns the exact discrete transition `A = exp(F*dt)` and process noise covariance
+/// `Q = integral(exp(F*t) * Q_cont * exp(F*t)^T, t = 0..dt)`. For noise entering through `G`,
+/// use `Q_cont = G*Q*G^T`.
+pub fn discretize<const Nx: usize, F: RealField + Copy>(
+    F_cont: &SMatrix<F, Nx, Nx>,
+    Q_cont: &SMatrix<F, Nx, Nx>,
+    dt: F,
+) -> (SMatrix<F, Nx, Nx>, SMatrix<F, Nx, Nx>) {
+    // exp([-F Q_cont; 0 F^T] * dt) = [.. A^-1*Q; 0 A^T]
+    let (_, M12, M22) = linalg::block_triangular_exponential(
+        &(-F_cont * dt),
+        &(Q_cont * dt),
+        &(F_cont.transpose() * dt),
+    );
+    let A = M22.transpose();
+    let Q = A * M12;
+    (A, (Q + Q.transpose()).scale(nalgebra::convert(0.5)))
+}
+
+/// Estimate the measurement noise covariance `R` as the sample covariance of measurements
+/// collected while the system is held stationary. Returns a zero matrix for fewer than two
+/// measurements.
+pub fn calibrate_r<const Ny: usize, F: Scalar + SimdValue + ComplexField + Copy>(
+    measurements: &[SMatrix<F, Ny, 1>],
+) -> SMatrix<F, Ny, Ny> {
+    if measurements.len() < 2 {
+        return SMatrix::zeros();
+    }
+    let n: F = nalgebra::convert(measurements.len() as f64);
+    let mean = measurements.iter().fold(SMatrix::zeros(), |acc, y| acc + y) / n;
+    let scatter = measurements.iter().fold(SMatrix::zeros(), |acc, y| {
+        let d = y - mean;
+        acc + d * d.transpose()
+    });
+    scatter / (n - F::one())
+}
+
+/// Intermediate quantities of a predict or update, passed to the callback set with
+/// `KalmanFilter::set_trace`. Matrices are given as slices in column-major order.
+#[cfg(feature = "trace")]
+#[derive(Debug)]
+pub enum TraceEvent<'a, F> {
+    /// Prediction, with the resulting prior state and covariance
+    Predict {
+        x: &'a [F],
+        P: &'a [F],
+    },
+
+    /// Update, with the measurement residual, innovation covariance, Kalman
+    /// gain and the resulting posterior state and covariance
+    Update {
+        y_res: &'a [F],
+        S: &'a [F],
+        K: &'a [F],
+        x: &'a [F],
+        P: &'a [F],
+    },
+}
+
+/// Step of a planned schedule for covariance-only simulation with `.simulate_covariance`.
+/// Measurement types with fewer than `Ny` outputs can be padded with zero rows of `C`
+/// and unit variance rows of `R`.
+pub enum CovarianceStep<'a, const Nx: usize, const Ny: usize, F: RealField + Copy> {
+    /// Prediction of the model
+    Predict,
+
+    /// Update with the measurement model `C` and `R`
+    Update {
+        C: &'a SMatrix<F, Ny, Nx>,
+        R: &'a SMatrix<F, Ny, Ny>,
+    },
+}
+
+/// Step of a recorded run replayed with `.run`. The measurement, if any, is fused first, after
+/// which the model is predicted with the input `u`. A step with a measurement but no input is
+/// update-only, while a step without either is a prediction without input.
+pub struct FilterStep<'a, const Nx: usize, const Nu: usize, const Ny: usize, F: RealField + Copy> {
+    /// Input of the prediction
+    pub u: Option<SMatrix<F, Nu, 1>>,
+
+    /// Measurement model `C`, `R` and measurement `y` of the update
+    pub measurement: Option<(&'a SMatrix<F, Ny, Nx>, &'a SMatrix<F, Ny, Ny>, SMatrix<F, Ny, 1>)>,
 }
 
 /// Linear state-space `Nx`-dimensional Kalman filter implementation utilizing the `nalgebra` library.
-pub struct KalmanFilter<const Nx: usize, const Nu: usize, F: Scalar + SimdValue + ComplexField + Copy> {
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KalmanFilter<const Nx: usize, const Nu: usize, F: RealField + Copy> {
 
     // Model propagation matrix
     A: SMatrix<F, Nx, Nx>,
@@ -26,9 +221,106 @@ pub struct KalmanFilter<const Nx: usize, const Nu: usize, F: Scalar + SimdValue
     // A posteriori state vector and covariance matrix
     post: Option<VecMat<Nx, F>>,
 
+    // Time step of each prediction
+    dt: F,
+
+    // Time of the current estimate
+    time: F,
+
+    // Continuous-time model `(F, B, Q)` discretized for each step of `.predict_dt`
+    continuous_model: Option<(SMatrix<F, Nx, Nx>, SMatrix<F, Nx, Nu>, SMatrix<F, Nx, Nx>)>,
+
+    // Fraction of the state replaced by the latest measurement
+    influence: F,
+
+    // Accumulated Frobenius norm of the Kalman gains of all updates
+    cumulative_gain_norm: F,
+
+    // Current inflation of `Q`, decaying back towards one
+    q_inflation: F,
+
+    // Inflation factor of the measurement noise covariance `R`
+    r_inflation: F,
+
+    // Fading-memory factor scaling the propagated covariance `A*P*A^T`
+    forgetting_factor: F,
+
+    // NIS threshold triggering and decay rate of the inflation of `Q`
+    q_inflation_trigger: Option<(F, F)>,
+
+    // Window length in updates and maximum scale of the adaptive rescaling of `Q`
+    adaptive_q: Option<(usize, F)>,
+
+    // Current adaptive scale of `Q`, and the update count and NIS sum of the window being filled
+    adaptive_q_scale: F,
+    adaptive_q_window: (usize, F),
+
+    // Maximum condition number of `S` accepted by debug validation of measurements
+    validation: Option<F>,
+
+    // Time constant in updates of the moving average of the NIS
+    nis_time_constant: Option<F>,
+
+    // Exponentially weighted moving average of the NIS
+    smoothed_nis: Option<F>,
+
+    // Standardized innovation of the last update, padded by zeros to `Nx` components, and its
+    // number of components `Ny`, if at most `Nx`
+    last_standardized_innovation: Option<(SMatrix<F, Nx, 1>, usize)>,
+
+    // Number of consecutive predictions without an accepted measurement
+    steps_without_update: usize,
+
+    // Number of measurements rejected by gating or by a singular innovation covariance
+    rejected: usize,
+
+    // Callback tracing each predict and update, not serialized
+    #[cfg(feature = "trace")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    trace: Option<fn(&TraceEvent<'_, F>)>,
+
+    // Feasible box `(min, max)` the mean is clamped into when forecasting
+    forecast_bounds: Option<(SMatrix<F, Nx, 1>, SMatrix<F, Nx, 1>)>,
+
+    // Feasible box `(min, max)` the mean is clamped into after each predict and update
+    state_bounds: Option<(SMatrix<F, Nx, 1>, SMatrix<F, Nx, 1>)>,
+
+    // Linear equality constraint `D*x = d` the mean is projected onto after each update,
+    // with `D` padded by zero rows to `Nx` rows
+    equality_constraint: Option<(SMatrix<F, Nx, Nx>, SMatrix<F, Nx, 1>)>,
+
+    // Cross-covariance `M = E[w * v^T]` of the process and measurement noise, with `M` padded by
+    // zero columns to `Nx` columns, and the number of measurements `Ny` it applies to
+    cross_covariance: Option<(SMatrix<F, Nx, Nx>, usize)>,
+
+    // Per-state variance ceiling applied after each prediction
+    variance_ceiling: Option<SMatrix<F, Nx, 1>>,
+
+    // Information `(sum C^T * R^-1 * y, sum C^T * R^-1 * C)` of measurements pending until the next prediction
+    accumulated: Option<(SMatrix<F, Nx, 1>, SMatrix<F, Nx, Nx>)>,
+
+}
+
+/// Shows the model and the current estimate, leaving out the configuration.
+impl<const Nx: usize, const Nu: usize, F: RealField + Copy> core::fmt::Debug for KalmanFilter<Nx, Nu, F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("KalmanFilter")
+            .field("A", &self.A)
+            .field("B", &self.B)
+            .field("Q", &self.Q)
+            .field("x", &self.get_state())
+            .field("P", &self.covariance())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<const Nx: usize, const Nu: usize, F: RealField + Copy> Default for KalmanFilter<Nx, Nu, F> {
+    fn default() -> Self {
+        Self::zeros()
+    }
 }
 
-impl<const Nx: usize, const Nu: usize, F: Scalar + SimdValue + ComplexField + Copy> KalmanFilter<Nx, Nu, F> {
+impl<const Nx: usize, const Nu: usize, F: RealField + Copy> KalmanFilter<Nx, Nu, F> {
     /// Provide kalman filter with all initial values
     pub fn new(
         A: SMatrix<F, Nx, Nx>,
@@ -49,7 +341,83 @@ impl<const Nx: usize, const Nu: usize, F: Scalar + SimdValue + ComplexField + Co
                 P: P_init,
             },
             post: None,
+            dt: F::one(),
+            time: F::zero(),
+            continuous_model: None,
+            influence: F::zero(),
+            cumulative_gain_norm: F::zero(),
+            q_inflation: F::one(),
+            r_inflation: F::one(),
+            forgetting_factor: F::one(),
+            q_inflation_trigger: None,
+            adaptive_q: None,
+            adaptive_q_scale: F::one(),
+            adaptive_q_window: (0, F::zero()),
+            validation: None,
+            nis_time_constant: None,
+            smoothed_nis: None,
+            last_standardized_innovation: None,
+            steps_without_update: 0,
+            rejected: 0,
+            #[cfg(feature = "trace")]
+            trace: None,
+            forecast_bounds: None,
+            state_bounds: None,
+            equality_constraint: None,
+            cross_covariance: None,
+            variance_ceiling: None,
+            accumulated: None,
+        }
+    }
+
+    /// Provide kalman filter with an identity `A` and `P_init`, and a zero `B`, `Q` and `x_init`,
+    /// to be configured with the `set_*` methods.
+    pub fn zeros() -> Self {
+        Self::new(SMatrix::identity(), None, SMatrix::zeros(), SMatrix::zeros(), SMatrix::identity())
+    }
+
+    /// Provide kalman filter with all initial values, verifying that `P_init` is symmetric
+    /// positive-definite with variances of at most `max_variance`.
+    pub fn new_checked(
+        A: SMatrix<F, Nx, Nx>,
+        B: Option<SMatrix<F, Nx, Nu>>,
+        Q: SMatrix<F, Nx, Nx>,
+        x_init: SMatrix<F, Nx, 1>,
+        P_init: SMatrix<F, Nx, Nx>,
+        max_variance: F,
+    ) -> Result<Self, KalmanError> {
+        let tol = F::default_epsilon().sqrt() * P_init.norm();
+        let symmetric = (P_init - P_init.transpose()).norm() <= tol;
+        let bounded = P_init.diagonal().iter().all(|&v| v <= max_variance);
+        if !symmetric || !bounded || P_init.cholesky().is_none() {
+            return Err(KalmanError::InvalidInitialCovariance);
         }
+        Ok(Self::new(A, B, Q, x_init, P_init))
+    }
+
+    /// Provide kalman filter with all initial values, projecting `P_init` onto the nearest valid
+    /// covariance with eigenvalues of at least `min_eigenvalue` using `nearest_covariance`.
+    /// Also returns whether `P_init` was invalid and had to be projected.
+    pub fn new_projected(
+        A: SMatrix<F, Nx, Nx>,
+        B: Option<SMatrix<F, Nx, Nu>>,
+        Q: SMatrix<F, Nx, Nx>,
+        x_init: SMatrix<F, Nx, 1>,
+        P_init: SMatrix<F, Nx, Nx>,
+        min_eigenvalue: F,
+    ) -> (Self, bool) {
+        let P = nearest_covariance(&P_init, min_eigenvalue);
+        let projected = (P - P_init).norm() > F::default_epsilon().sqrt() * P_init.norm();
+        (Self::new(A, B, Q, x_init, if projected { P } else { P_init }), projected)
+    }
+
+    /// Reinitialize the estimate in place to `x_init` and `P_init`, such as after a fault or
+    /// a discontinuity in the state. Any updates since the last prediction, and measurements
+    /// pending with `.accumulate`, are discarded. The model and configuration are kept.
+    pub fn reset(&mut self, x_init: SMatrix<F, Nx, 1>, P_init: SMatrix<F, Nx, Nx>) {
+        self.prio = VecMat { x: x_init, P: P_init };
+        self.post = None;
+        self.accumulated = None;
     }
 
     pub fn set_A(&mut self, new_A : SMatrix<F, Nx, Nx>) {
@@ -63,6 +431,267 @@ impl<const Nx: usize, const Nu: usize, F: Scalar + SimdValue + ComplexField + Co
         };
     }
 
+    /// Set the model noise covariance matrix. As `Q` is only used when predicting, it can be
+    /// changed at any point, such as between predictions or between an update and a prediction,
+    /// and takes effect from the next prediction.
+    pub fn set_Q(&mut self, new_Q : SMatrix<F, Nx, Nx>) {
+        self.Q = new_Q;
+    }
+
+    /// Get the model propagation matrix `A`.
+    pub fn get_A(&self) -> SMatrix<F, Nx, Nx> {
+        self.A
+    }
+
+    /// Get the input matrix `B`, which is zero for a model without inputs.
+    pub fn get_B(&self) -> SMatrix<F, Nx, Nu> {
+        self.B
+    }
+
+    /// Get the nominal model noise covariance matrix `Q`, without any inflation or adaptive scaling.
+    pub fn get_Q(&self) -> SMatrix<F, Nx, Nx> {
+        self.Q
+    }
+
+    /// Set the time step each prediction advances the filter time by.
+    /// Defaults to one, such that the filter time counts predictions.
+    pub fn set_time_step(&mut self, dt: F) {
+        self.dt = dt;
+    }
+
+    /// Set the continuous-time model `x_dot = F*x + B*u + w`, with white process noise `w` of power
+    /// spectral density `Q_cont`, used by `.predict_dt` for steps of irregular length. A missing
+    /// `B_cont` is taken as a system without inputs.
+    pub fn set_continuous_model(
+        &mut self,
+        F_cont: SMatrix<F, Nx, Nx>,
+        B_cont: Option<SMatrix<F, Nx, Nu>>,
+        Q_cont: SMatrix<F, Nx, Nx>,
+    ) {
+        self.continuous_model = Some((F_cont, B_cont.unwrap_or_else(SMatrix::zeros), Q_cont));
+    }
+
+    /// Predict new state over a time step `dt` using input, held constant over the step. The
+    /// continuous model set with `.set_continuous_model` is discretized for this `dt` with
+    /// `discretize`, replacing `A`, `B`, `Q` and the time step of the filter, before predicting.
+    ///
+    /// Returns `KalmanError::InvalidTimeStep` if `dt` is not positive and finite, or
+    /// `KalmanError::NoContinuousModel` if no continuous model is set, leaving the filter unchanged.
+    pub fn predict_dt(&mut self, dt: F, u: SMatrix<F, Nu, 1>) -> Result<(), KalmanError> {
+        if !(dt > F::zero() && dt.is_finite()) {
+            return Err(KalmanError::InvalidTimeStep);
+        }
+        let (F_cont, B_cont, Q_cont) = self.continuous_model.ok_or(KalmanError::NoContinuousModel)?;
+        let (A, Q) = discretize(&F_cont, &Q_cont, dt);
+
+        // Zero-order hold input matrix `integral(exp(F*t), t = 0..dt) * B` from the
+        // exponential of `[F*dt I*dt; 0 0]`
+        let (_, Phi, _) = linalg::block_triangular_exponential(
+            &(F_cont * dt),
+            &(SMatrix::identity() * dt),
+            &SMatrix::zeros(),
+        );
+
+        self.A = A;
+        self.B = Phi * B_cont;
+        self.Q = Q;
+        self.dt = dt;
+        self.predict_with_input(u);
+        Ok(())
+    }
+
+    /// Enable temporary inflation of `Q` when the normalized innovation squared (NIS) of an
+    /// update exceeds `threshold`. The next prediction then uses `Q` scaled by `NIS / threshold`,
+    /// after which the inflation decays geometrically back towards `Q` with the given `decay`
+    /// rate in `[0, 1)` per prediction.
+    pub fn set_q_inflation(&mut self, threshold: F, decay: F) {
+        self.q_inflation_trigger = Some((threshold, decay));
+    }
+
+    /// Disable the innovation based inflation of `Q`, returning to the nominal `Q`.
+    pub fn disable_q_inflation(&mut self) {
+        self.q_inflation_trigger = None;
+        self.q_inflation = F::one();
+    }
+
+    /// Get the current inflation factor of `Q`.
+    pub fn get_q_inflation(&self) -> F {
+        self.q_inflation
+    }
+
+    /// Enable adaptive rescaling of `Q` from the innovations, for process noise that changes over
+    /// time. The mean normalized innovation squared per measurement component, which is one for a
+    /// consistent filter, is collected over blocks of `window` updates. At the end of each block
+    /// the scale of `Q` is multiplied by this mean, and clamped to `[1/scale, scale]` to avoid
+    /// runaway growth. The nominal `Q` is used until the first block is complete.
+    ///
+    /// The blocks are disjoint rather than a sliding window by design. Each innovation is
+    /// normalized by an `S` already predicted with the current scale, so the block mean measures
+    /// the remaining mismatch and is compounded onto the scale. A sliding window would apply each
+    /// innovation to `window` consecutive rescalings and overcorrect, and would need a buffer of
+    /// `window` innovations where the blocks only need a count and a sum.
+    pub fn enable_adaptive_q(&mut self, window: usize, scale: F) {
+        self.adaptive_q = Some((window.max(1), scale));
+    }
+
+    /// Disable the adaptive rescaling of `Q`, returning to the nominal `Q`.
+    pub fn disable_adaptive_q(&mut self) {
+        self.adaptive_q = None;
+        self.adaptive_q_scale = F::one();
+        self.adaptive_q_window = (0, F::zero());
+    }
+
+    /// Get the current adaptive scale of `Q`.
+    pub fn get_adaptive_q_scale(&self) -> F {
+        self.adaptive_q_scale
+    }
+
+    /// Scale the measurement noise covariance `R` of all updates by `factor`, where a factor greater
+    /// than one makes the filter trust measurements less than their nominal `R`. Defaults to one.
+    pub fn set_measurement_inflation(&mut self, factor: F) {
+        self.r_inflation = factor;
+    }
+
+    /// Set the fading-memory factor `lambda >= 1` of the prediction, `P = lambda * A*P*A^T + Q`,
+    /// which discounts old information such that the filter stays responsive to unmodeled
+    /// dynamics. Defaults to one, which disables fading memory.
+    pub fn set_forgetting_factor(&mut self, lambda: F) {
+        self.forgetting_factor = lambda;
+    }
+
+    /// Set a callback invoked with the intermediate quantities of each predict and update,
+    /// for debugging. Pass `None` to disable tracing.
+    ///
+    /// Forward and backward predictions are traced, as are the updates computing an innovation
+    /// covariance, with one event per component for `.update_sequential`. Updates with a supplied
+    /// gain (`.update_with_gain`, `.update_state_only`), the joint fusion of accumulated
+    /// measurements and `.reset` are not traced, nor are measurements rejected by a gate, which
+    /// leave the estimate unchanged.
+    #[cfg(feature = "trace")]
+    pub fn set_trace(&mut self, callback: Option<fn(&TraceEvent<'_, F>)>) {
+        self.trace = callback;
+    }
+
+    /// Enable an exponentially weighted moving average of the normalized innovation squared
+    /// (NIS) of each update, with a time constant of `tau` updates. Read it with `.smoothed_nis`.
+    pub fn set_nis_time_constant(&mut self, tau: F) {
+        self.nis_time_constant = Some(tau);
+        self.smoothed_nis = None;
+    }
+
+    /// Get the moving average of the NIS, if enabled with `.set_nis_time_constant` and at least
+    /// one update has been made. A consistent filter has an average NIS close to `Ny`, so
+    /// sustained deviations indicate a mistuned filter.
+    pub fn smoothed_nis(&self) -> Option<F> {
+        self.smoothed_nis
+    }
+
+    /// Enable validation of the measurement model in `.update`, active in debug builds only.
+    /// An update where `R` is not symmetric positive-definite, or where the condition number of
+    /// the innovation covariance `S` exceeds `max_condition`, is then rejected with the error.
+    /// Pass `None` to disable validation.
+    pub fn set_debug_validation(&mut self, max_condition: Option<F>) {
+        self.validation = max_condition;
+    }
+
+    /// Clamp the predicted mean component-wise into the box `[min, max]` at each step of
+    /// `.predict_n` and `.forecast_iter`, keeping forecasts within a feasible set. Unbounded
+    /// components can use infinite limits. This does not affect `.predict` or `.update`.
+    pub fn set_forecast_bounds(&mut self, min: SMatrix<F, Nx, 1>, max: SMatrix<F, Nx, 1>) {
+        self.forecast_bounds = Some((min, max));
+    }
+
+    /// Remove the forecast bounds set with `.set_forecast_bounds`.
+    pub fn clear_forecast_bounds(&mut self) {
+        self.forecast_bounds = None;
+    }
+
+    /// Clamp the mean component-wise into the box `[min, max]` after each predict and update,
+    /// such as for physically bounded states. Only the mean is projected, so the covariance may
+    /// still extend beyond the bounds. Unbounded components can use infinite limits.
+    pub fn set_state_bounds(&mut self, min: SMatrix<F, Nx, 1>, max: SMatrix<F, Nx, 1>) {
+        self.state_bounds = Some((min, max));
+    }
+
+    /// Remove the state bounds set with `.set_state_bounds`.
+    pub fn clear_state_bounds(&mut self) {
+        self.state_bounds = None;
+    }
+
+    /// Constrain the mean to the `Nc` linear equality constraints `D*x = d`, such as velocities
+    /// summing to zero, by the covariance weighted projection
+    /// `x = x - P*D^T * (D*P*D^T)^-1 * (D*x - d)` after each update. Only the mean is projected.
+    /// A rank-deficient `D*P*D^T`, such as from redundant constraints, is inverted with its
+    /// pseudo-inverse, such that constraints only on states without uncertainty are skipped.
+    pub fn set_equality_constraint<const Nc: usize>(&mut self, D: &SMatrix<F, Nc, Nx>, d: &SMatrix<F, Nc, 1>) {
+        const { assert!(Nc <= Nx, "number of constraints `Nc` must be at most `Nx`") };
+        let mut D_padded = SMatrix::<F, Nx, Nx>::zeros();
+        D_padded.fixed_view_mut::<Nc, Nx>(0, 0).copy_from(D);
+        let mut d_padded = SMatrix::<F, Nx, 1>::zeros();
+        d_padded.fixed_view_mut::<Nc, 1>(0, 0).copy_from(d);
+        self.equality_constraint = Some((D_padded, d_padded));
+    }
+
+    /// Remove the equality constraint set with `.set_equality_constraint`.
+    pub fn clear_equality_constraint(&mut self) {
+        self.equality_constraint = None;
+    }
+
+    /// Set the cross-covariance `M = E[w * v^T]` between the process noise of each prediction and
+    /// the noise of the `Ny` measurements following it, applied to measurements of `Ny` components
+    /// by the updates, the gates and the innovation diagnostics alike. The innovation covariance
+    /// becomes `S = C*P*C^T + C*M + M^T*C^T + R` and the gain `K = (P*C^T + M) * S^-1`, with the
+    /// covariance updated as `P - K*(C*P + M^T)`. Measurements of other dimensions are unaffected,
+    /// as are updates with a supplied gain.
+    pub fn set_cross_covariance<const Ny: usize>(&mut self, M: &SMatrix<F, Nx, Ny>) {
+        const { assert!(Ny <= Nx, "number of measurements `Ny` must be at most `Nx`") };
+        let mut M_padded = SMatrix::<F, Nx, Nx>::zeros();
+        M_padded.fixed_view_mut::<Nx, Ny>(0, 0).copy_from(M);
+        self.cross_covariance = Some((M_padded, Ny));
+    }
+
+    /// Remove the cross-covariance set with `.set_cross_covariance`.
+    pub fn clear_cross_covariance(&mut self) {
+        self.cross_covariance = None;
+    }
+
+    /// Cap the variance of each state at `max` after each `.predict`, such that the uncertainty
+    /// saturates rather than growing without bound during long gaps between measurements.
+    /// States above their ceiling have their rows and columns of `P` scaled down, which keeps
+    /// the correlations and the covariance positive semi-definite.
+    pub fn set_variance_ceiling(&mut self, max: SMatrix<F, Nx, 1>) {
+        self.variance_ceiling = Some(max);
+    }
+
+    /// Remove the variance ceiling set with `.set_variance_ceiling`.
+    pub fn clear_variance_ceiling(&mut self) {
+        self.variance_ceiling = None;
+    }
+
+    /// Check that `R` is symmetric positive-definite and that the resulting innovation
+    /// covariance `S` has a condition number of at most `max_condition`.
+    pub fn validate_measurement<const Ny: usize>(
+        &self,
+        C: &SMatrix<F, Ny, Nx>,
+        R: &SMatrix<F, Ny, Ny>,
+        max_condition: F,
+    ) -> Result<(), KalmanError> {
+        // Symmetric to within roundoff, and positive-definite
+        let tol = F::default_epsilon().sqrt() * R.norm();
+        if (R - R.transpose()).norm() > tol || R.cholesky().is_none() {
+            return Err(KalmanError::NonPositiveDefiniteNoise);
+        }
+
+        let S = C * self.covariance() * C.transpose() + R;
+        let (eigenvalues, _) = linalg::symmetric_eigen(&S);
+        let max = eigenvalues.iter().fold(F::zero(), |acc, e| acc.max(e.abs()));
+        let min = eigenvalues.iter().fold(max, |acc, e| acc.min(e.abs()));
+        if min.is_zero() || max / min > max_condition {
+            return Err(KalmanError::IllConditionedInnovation);
+        }
+        Ok(())
+    }
+
     /// Predict new state. If plant dynamics are time-dependent,
     /// this method (or `.predict_with_input`) must be called at the correct frequency.
     pub fn predict(&mut self) {
@@ -73,64 +702,1603 @@ impl<const Nx: usize, const Nu: usize, F: Scalar + SimdValue + ComplexField + Co
     /// Predict new state using input. If plant dynamics are time-dependent,
     /// this method (or `.predict`) must be called at the correct frequency.
     pub fn predict_with_input(&mut self, u : SMatrix<F, Nu, 1>) {
-        match self.post.as_mut() {
+        let B = self.B;
+        self.predict_with_input_and_matrix(u, &B)
+    }
+
+    /// Predict new state using input through the input matrix `B` of this step only, such as for
+    /// a rotating thrust direction. The stored input matrix is left unchanged.
+    pub fn predict_with_input_and_matrix(&mut self, u: SMatrix<F, Nu, 1>, B: &SMatrix<F, Nx, Nu>) {
+        self.apply_accumulated();
+
+        // Take posteriors, setting them to none
+        match self.post.take() {
             // Simple prediction, no new observations
             None => {
-                self.prio.x = self.A * self.prio.x + self.B*u;
-                self.prio.P = self.A * self.prio.P * self.A.transpose() + self.Q;
+                self.prio.x = self.A * self.prio.x + B*u;
+                self.prio.P = self.inflate_covariance(self.A * self.prio.P * self.A.transpose());
+                self.steps_without_update += 1;
             }
 
             // Prediction based on new observations
-            Some(post) => {
-                // Finish calc for P_post and symmetrize
-                post.P = post.P * self.prio.P;
-
+            Some(mut post) => {
                 // Symmetrize
                 post.P = (post.P + post.P.transpose()).scale(nalgebra::convert(0.5));
 
                 // Update priors
-                self.prio.x = self.A * post.x + self.B*u;
-                self.prio.P = self.A * post.P * self.A.transpose() + self.Q;
+                self.prio.x = self.A * post.x + B*u;
+                self.prio.P = self.inflate_covariance(self.A * post.P * self.A.transpose());
+            }
+        }
+
+        self.prio.x = clamp(self.prio.x, self.state_bounds.as_ref());
+
+        self.prio.P = self.saturate_variances(self.prio.P);
+        self.decay_q_inflation();
+
+        self.time += self.dt;
+
+        #[cfg(feature = "trace")]
+        if let Some(trace) = self.trace {
+            trace(&TraceEvent::Predict { x: self.prio.x.as_slice(), P: self.prio.P.as_slice() });
+        }
+    }
+
+    /// Predict the state one step backward in time using the inverse dynamics
+    /// `x[k-1] = A^-1 * (x[k] - B*u)`, with covariance `A^-1 * (lambda*P + Q) * A^-T` for the same
+    /// forgetting factor `lambda` and effective process noise `Q` as `.predict`. Like `.predict`,
+    /// the measurements accumulated with `.accumulate` are first fused at the current epoch.
+    /// Returns an error if `A` is not invertible, leaving the filter unchanged.
+    pub fn predict_backward(&mut self, u: SMatrix<F, Nu, 1>) -> Result<(), KalmanError> {
+        let Ainv = self.A.try_inverse().ok_or(KalmanError::SingularTransition)?;
+        self.apply_accumulated();
+        let x = self.get_state();
+        let P = self.inflate_covariance(self.covariance());
+
+        self.prio = VecMat {
+            x: Ainv * (x - self.B * u),
+            P: Ainv * P * Ainv.transpose(),
+        };
+        self.post = None;
+
+        self.time -= self.dt;
+
+        #[cfg(feature = "trace")]
+        if let Some(trace) = self.trace {
+            trace(&TraceEvent::Predict { x: self.prio.x.as_slice(), P: self.prio.P.as_slice() });
+        }
+        Ok(())
+    }
+
+    /// Predict through a schedule of inputs, applying `.predict_with_input` for each input in
+    /// sequence. Returns the final state.
+    pub fn run_inputs(&mut self, inputs: &[SMatrix<F, Nu, 1>]) -> SMatrix<F, Nx, 1> {
+        for u in inputs {
+            self.predict_with_input(*u);
+        }
+        self.get_state()
+    }
+
+    /// Predict through a schedule of inputs like `.run_inputs`, returning the state after each input.
+    #[cfg(feature = "alloc")]
+    pub fn run_inputs_collect(&mut self, inputs: &[SMatrix<F, Nu, 1>]) -> Vec<SMatrix<F, Nx, 1>> {
+        inputs
+            .iter()
+            .map(|u| {
+                self.predict_with_input(*u);
+                self.get_state()
+            })
+            .collect()
+    }
 
-                // Set posteriors to none
-                self.post = None;
+    /// Replay a recorded run of steps, each fusing its measurement and predicting with its input
+    /// as described by `FilterStep`. Returns the state after each step, or the error of the first
+    /// failing update, at which point the earlier steps have been applied.
+    #[cfg(feature = "alloc")]
+    pub fn run<const Ny: usize>(
+        &mut self,
+        steps: &[FilterStep<'_, Nx, Nu, Ny, F>],
+    ) -> Result<Vec<SMatrix<F, Nx, 1>>, KalmanError> {
+        let mut states = Vec::with_capacity(steps.len());
+        for step in steps {
+            if let Some((C, R, y)) = step.measurement {
+                self.update(C, R, &y)?;
+            }
+            match (step.u, step.measurement) {
+                (Some(u), _) => self.predict_with_input(u),
+                (None, None) => self.predict(),
+                (None, Some(_)) => {}
             }
+            states.push(self.get_state());
         }
+        Ok(states)
     }
 
-    /// Update filter with new measurements
+    /// Update filter with new measurements.
+    ///
+    /// Multiple updates between predictions are applied sequentially, each against the posterior
+    /// of the previous one. For measurements with mutually uncorrelated noise this is equivalent to
+    /// a single update with all the measurements stacked, regardless of their order.
+    ///
+    /// Returns `KalmanError::SingularInnovation` and leaves the estimate unchanged if the
+    /// innovation covariance `S` cannot be inverted.
     pub fn update<const Ny: usize>(
         &mut self,
         C: &SMatrix<F, Ny, Nx>, // Output matrix
         R: &SMatrix<F, Ny, Ny>, // Covariance
         y: &SMatrix<F, Ny, 1>, // Measurement
-    ) {
-        // Measurement prediction residual
-        let y_res = y - C * self.prio.x;
+    ) -> Result<(), KalmanError> {
+        self.fuse(C, R, y, false)
+    }
+
+    /// Update filter with new measurements, using the Joseph form of the covariance update
+    /// `P = (I - K*C) * P * (I - K*C)^T + K*R*K^T`. This is slower than `.update`, but keeps the
+    /// covariance symmetric positive-definite under roundoff, such as in long `f32` runs.
+    pub fn update_joseph<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> Result<(), KalmanError> {
+        self.fuse(C, R, y, true)
+    }
+
+    /// Update filter with the simultaneous measurements of two sensors in a single correction.
+    /// The models are stacked into `C = [C1; C2]`, `y = [y1; y2]` and the block-diagonal
+    /// `R = diag(R1, R2)`, so `Ny` must equal `Ny1 + Ny2`. Sensors with correlated noise
+    /// can instead be stacked by hand and passed to `.update` with the full `R`.
+    pub fn update_batch<const Ny1: usize, const Ny2: usize, const Ny: usize>(
+        &mut self,
+        (C1, R1, y1): (&SMatrix<F, Ny1, Nx>, &SMatrix<F, Ny1, Ny1>, &SMatrix<F, Ny1, 1>),
+        (C2, R2, y2): (&SMatrix<F, Ny2, Nx>, &SMatrix<F, Ny2, Ny2>, &SMatrix<F, Ny2, 1>),
+    ) -> Result<(), KalmanError> {
+        const { assert!(Ny == Ny1 + Ny2, "stacked dimension `Ny` must equal `Ny1 + Ny2`") };
+
+        let mut C = SMatrix::<F, Ny, Nx>::zeros();
+        C.fixed_view_mut::<Ny1, Nx>(0, 0).copy_from(C1);
+        C.fixed_view_mut::<Ny2, Nx>(Ny1, 0).copy_from(C2);
 
-        // Innovation (or pre-fit residual) covariance
-        let S = C * self.prio.P * C.transpose() + R;
+        let mut R = SMatrix::<F, Ny, Ny>::zeros();
+        R.fixed_view_mut::<Ny1, Ny1>(0, 0).copy_from(R1);
+        R.fixed_view_mut::<Ny2, Ny2>(Ny1, Ny1).copy_from(R2);
+
+        let mut y = SMatrix::<F, Ny, 1>::zeros();
+        y.fixed_view_mut::<Ny1, 1>(0, 0).copy_from(y1);
+        y.fixed_view_mut::<Ny2, 1>(Ny1, 0).copy_from(y2);
+
+        self.update(&C, &R, &y)
+    }
+
+    /// Update filter with new measurements with uncorrelated noise of variances `R_diag`, applying
+    /// each component in turn as a scalar update. This only needs scalar reciprocals rather than
+    /// the inverse of `S`, and matches `.update` with the diagonal covariance `R = diag(R_diag)`.
+    ///
+    /// Returns `KalmanError::SingularInnovation` if the innovation variance of a component is
+    /// zero, in which case the components before it have already been applied.
+    pub fn update_sequential<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>,      // Output matrix
+        R_diag: &SMatrix<F, Ny, 1>,  // Variances
+        y: &SMatrix<F, Ny, 1>,       // Measurement
+    ) -> Result<(), KalmanError> {
+        let mut influence = F::zero();
+        let mut M = self.noise_cross_covariance::<Ny>();
+        for i in 0..Ny {
+            let c: SMatrix<F, 1, Nx> = C.fixed_rows::<1>(i).into_owned();
+            let m = M.as_ref().map(|M| M.column(i).into_owned());
+            let P = self.covariance();
+            let mut PCt = P * c.transpose();
+            let mut s = (c * PCt)[0] + R_diag[i] * self.r_inflation;
+            if let Some(m) = m.as_ref() {
+                s += (c * m)[0] * nalgebra::convert(2.0);
+                PCt += m;
+            }
+            if s.is_zero() {
+                self.rejected += 1;
+                return Err(KalmanError::SingularInnovation);
+            }
+
+            let K = PCt / s;
+            influence += (K * c).trace();
+            let y_res = SMatrix::<F, 1, 1>::new(y[i] - (c * self.get_state())[0]);
+            self.correct(&K, &c, m.as_ref(), &y_res);
+
+            #[cfg(feature = "trace")]
+            self.trace_update(&y_res, &SMatrix::<F, 1, 1>::new(s), &K);
+
+            // Correlation of the remaining components with the corrected estimate
+            if let Some(M) = M.as_mut() {
+                *M -= K * (c * *M);
+            }
+        }
+        self.influence = influence / nalgebra::convert(Nx as f64);
+        Ok(())
+    }
+
+    /// Update filter with new measurements, using either the standard or the Joseph form.
+    fn fuse<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>,
+        R: &SMatrix<F, Ny, Ny>,
+        y: &SMatrix<F, Ny, 1>,
+        joseph: bool,
+    ) -> Result<(), KalmanError> {
+        #[cfg(debug_assertions)]
+        if let Some(max_condition) = self.validation {
+            self.validate_measurement(C, R, max_condition)?;
+        }
+
+        // Measurement prediction residual and innovation (or pre-fit residual) covariance,
+        // against the current estimate such that sequential updates compose
+        let (y_res, S) = self.innovation(C, R, y);
 
         // Optimal Kalman gain
-        let Some(Sinv) = S.try_inverse() else { return };
-        let K = self.prio.P * C.transpose() * Sinv;
-
-        // Updated (a posteriori) estimate covariance
-        self.post = Some(match self.post.as_mut() {
-            Some(post) => VecMat {
-                x: post.x + K * y_res,
-                P: post.P - K * C,
-            },
-            None => VecMat {
-                x: self.prio.x + K * y_res,
-                P: SMatrix::identity() - K * C,
-            },
+        let Some(Sinv) = S.try_inverse() else {
+            self.rejected += 1;
+            return Err(KalmanError::SingularInnovation);
+        };
+        let K = self.gain(C, &Sinv);
+        self.influence = (K * C).trace() / nalgebra::convert(Nx as f64);
+
+        // Inflate `Q` on large normalized innovation squared
+        let nis = (y_res.transpose() * Sinv * y_res)[0];
+        if let Some((threshold, _)) = self.q_inflation_trigger {
+            let inflation = nis / threshold;
+            if inflation > self.q_inflation {
+                self.q_inflation = inflation;
+            }
+        }
+
+        // Rescale `Q` on complete, disjoint blocks of innovations, compounding the remaining mismatch
+        if let Some((window, max_scale)) = self.adaptive_q {
+            let (count, sum) = &mut self.adaptive_q_window;
+            *count += 1;
+            *sum += nis / nalgebra::convert(Ny as f64);
+            if *count >= window {
+                let ratio = *sum / nalgebra::convert(window as f64);
+                self.adaptive_q_scale = (self.adaptive_q_scale * ratio).max(F::one() / max_scale).min(max_scale);
+                self.adaptive_q_window = (0, F::zero());
+            }
+        }
+
+        // Standardized innovation, if it fits in `Nx` components
+        self.last_standardized_innovation = (Ny <= Nx).then(|| {
+            (SMatrix::from_fn(|i, _| if i < Ny { y_res[i] / S[(i, i)].sqrt() } else { F::zero() }), Ny)
+        });
+
+        // Moving average of the normalized innovation squared
+        if let Some(tau) = self.nis_time_constant {
+            let alpha = F::one() - (-F::one() / tau).exp();
+            self.smoothed_nis = Some(match self.smoothed_nis {
+                Some(smoothed) => smoothed + (nis - smoothed) * alpha,
+                None => nis,
+            });
+        }
+
+        let M = self.noise_cross_covariance::<Ny>();
+        if joseph {
+            self.correct_joseph(&K, C, &(R * self.r_inflation), M.as_ref(), &y_res);
+        } else {
+            self.correct(&K, C, M.as_ref(), &y_res);
+        }
+
+        #[cfg(feature = "trace")]
+        self.trace_update(&y_res, &S, &K);
+        Ok(())
+    }
+
+    /// Accumulate a measurement to be fused at the end of the prediction interval, jointly with
+    /// the other measurements accumulated within the interval, as if stacked into one update with
+    /// mutually uncorrelated noise. Measurements of different dimensions can be accumulated, and
+    /// are applied by the next `.predict` or `.apply_accumulated`.
+    /// Returns an error if `R` is not positive-definite, without accumulating the measurement.
+    pub fn accumulate<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> Result<(), KalmanError> {
+        let chol = (R * self.r_inflation).cholesky().ok_or(KalmanError::NonPositiveDefiniteNoise)?;
+        let (i, I) = self.accumulated.unwrap_or((SMatrix::zeros(), SMatrix::zeros()));
+        self.accumulated = Some((
+            i + C.transpose() * chol.solve(y),
+            I + C.transpose() * chol.solve(C),
+        ));
+        Ok(())
+    }
+
+    /// Fuse the measurements accumulated with `.accumulate` as a single joint update.
+    /// This is done automatically by `.predict`, and does nothing if no measurements are pending.
+    pub fn apply_accumulated(&mut self) {
+        let Some((i, I)) = self.accumulated.take() else { return };
+        let x = self.get_state();
+
+        // Always invertible for a positive semi-definite covariance
+        let Some(M) = (SMatrix::identity() + self.covariance() * I).try_inverse() else { return };
+        let P = M * self.covariance();
+        self.influence = (P * I).trace() / nalgebra::convert(Nx as f64);
+        self.steps_without_update = 0;
+        self.post = Some(VecMat {
+            x: self.constrain(x + P * (i - I * x), &P),
+            P: (P + P.transpose()).scale(nalgebra::convert(0.5)),
         });
     }
 
-    /// Get state vector `x`.
-    /// Returns the posterior estimate if it exists, otherwise returns priori prediction.
-    pub fn get_state(&self) -> SMatrix<F, Nx, 1> {
-        self.post.as_ref().unwrap_or(&self.prio).x
+    /// Fuse an external fix of the states where `mask` is `true`, such as a surveyed position,
+    /// as a pseudo-measurement `x_fix` with covariance `R_fix`. Unfixed states are still corrected
+    /// through their correlation with the fixed states. Rows and columns of `x_fix` and `R_fix`
+    /// belonging to unfixed states are ignored.
+    pub fn fuse_fix(
+        &mut self,
+        x_fix: &SMatrix<F, Nx, 1>,  // State fix
+        R_fix: &SMatrix<F, Nx, Nx>, // Covariance of fix
+        mask: &[bool; Nx],          // Fixed states
+    ) -> Result<(), KalmanError> {
+        // Selection of the fixed states, with uninformative rows for unfixed states
+        let (C, R, y) = strip_rows(&SMatrix::identity(), R_fix, x_fix, mask);
+        self.update(&C, &R, &y)
+    }
+
+    /// Update filter with new measurements, gating each component independently. Component `i`
+    /// is applied only if its standardized innovation `|y_res_i| / sqrt(S_ii)` against the current
+    /// estimate is at most `thresholds[i]`, such that a single outlying channel does not reject
+    /// the other channels of the measurement.
+    ///
+    /// Returns which components were accepted, with all of them rejected if the update with the
+    /// accepted components fails. Each rejected component counts once in `.rejected_count`.
+    pub fn update_gated_components<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+        thresholds: &[F; Ny],
+    ) -> [bool; Ny] {
+        let z = self.standardize_innovation(C, R, y);
+        let mut accepted: [bool; Ny] = core::array::from_fn(|i| z[i].abs() <= thresholds[i]);
+        if accepted.iter().any(|&a| a) {
+            // A failed update rejects the accepted components too, each counted once below
+            let rejected = self.rejected;
+            let (C, R, y) = strip_rows(C, R, y, &accepted);
+            if self.update(&C, &R, &y).is_err() {
+                self.rejected = rejected;
+                accepted = [false; Ny];
+            }
+        }
+        self.rejected += accepted.iter().filter(|&&a| !a).count();
+        accepted
+    }
+
+    /// Update filter with new measurements, down-weighting outliers with a Huber influence function
+    /// for heavy-tailed measurement noise. A measurement with Mahalanobis distance `d` above
+    /// `huber_delta` is fused with its covariance `R` inflated by `d / huber_delta`, such that its
+    /// influence grows linearly rather than quadratically with the residual. Unlike the hard
+    /// decision of `.update_gated` outliers are still fused, with less weight.
+    ///
+    /// Returns `KalmanError::SingularInnovation` and leaves the estimate unchanged if the
+    /// innovation covariance `S` cannot be inverted.
+    pub fn update_robust<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+        huber_delta: F,
+    ) -> Result<(), KalmanError> {
+        let Some(d) = self.mahalanobis_distance(C, R, y) else {
+            self.rejected += 1;
+            return Err(KalmanError::SingularInnovation);
+        };
+        if d <= huber_delta {
+            self.update(C, R, y)
+        } else {
+            self.update(C, &(R * (d / huber_delta)), y)
+        }
+    }
+
+    /// Update filter with new measurements where unavailable components of `y` are `NaN` (or
+    /// otherwise not finite), such as for a multi-axis sensor reporting only some channels. The rows of `C`, `R` and `y` of those
+    /// components are dropped for this step, and an all-`NaN` measurement leaves the estimate
+    /// unchanged.
+    pub fn update_masked<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> Result<(), KalmanError> {
+        let available: [bool; Ny] = core::array::from_fn(|i| y[i].is_finite());
+        if !available.iter().any(|&a| a) {
+            return Ok(());
+        }
+        let (C, R, y) = strip_rows(C, R, y, &available);
+        self.update(&C, &R, &y)
+    }
+
+    /// Replace the covariance of the current estimate with its symmetric part `(P + P^T) / 2`,
+    /// such as periodically during long `f32` runs.
+    pub fn symmetrize(&mut self) {
+        let current = self.post.as_mut().unwrap_or(&mut self.prio);
+        current.P = (current.P + current.P.transpose()).scale(nalgebra::convert(0.5));
+    }
+
+    /// Project the covariance of the current estimate onto the nearest valid covariance with
+    /// eigenvalues of at least `min`, using `nearest_covariance`. This also symmetrizes it.
+    pub fn clamp_eigenvalues(&mut self, min: F) {
+        let current = self.post.as_mut().unwrap_or(&mut self.prio);
+        current.P = nearest_covariance(&current.P, min);
+    }
+
+    /// Blend the state a fraction `weight` in `[0, 1]` toward a reference prediction `x_ref`,
+    /// such as from a separate physics model. As the reference is not an independent
+    /// observation, the covariance is inflated by the spread between the estimate and the
+    /// reference, `P + weight*(1 - weight) * d*d^T` with `d = x_ref - x`.
+    pub fn blend_toward(&mut self, x_ref: &SMatrix<F, Nx, 1>, weight: F) {
+        let x = self.get_state();
+        let d = x_ref - x;
+        self.prio = VecMat {
+            x: x + d * weight,
+            P: self.covariance() + d * d.transpose() * (weight * (F::one() - weight)),
+        };
+        self.post = None;
+    }
+
+    /// Update filter with new measurements, resolving the ambiguity of a singular innovation
+    /// covariance `S`, such as from redundant noiseless measurements, in a preferred way. The
+    /// gain is then computed by pseudo-inverse with the covariance weighted by the preference
+    /// matrix `W` as `W*P*W^T`, steering the correction into the state directions spanned by `W`.
+    /// With an identity `W` this is the minimum-norm pseudo-inverse solution.
+    /// For a regular `S` this is the same as `.update`.
+    pub fn update_with_preference<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+        W: &SMatrix<F, Nx, Nx>, // Preference matrix
+    ) -> Result<(), KalmanError> {
+        let (y_res, S) = self.innovation(C, R, y);
+        if S.try_inverse().is_some() {
+            return self.update(C, R, y);
+        }
+
+        // Pseudo-inverse gain with the preference weighted covariance
+        let P = W * self.covariance() * W.transpose();
+        let S = C * P * C.transpose() + R * self.r_inflation;
+        let K = P * C.transpose() * linalg::symmetric_pseudo_inverse(&S);
+        self.influence = (K * C).trace() / nalgebra::convert(Nx as f64);
+
+        // Joseph form, as the gain is not optimal
+        let M = self.noise_cross_covariance::<Ny>();
+        self.correct_joseph(&K, C, &(R * self.r_inflation), M.as_ref(), &y_res);
+
+        #[cfg(feature = "trace")]
+        self.trace_update(&y_res, &S, &K);
+        Ok(())
+    }
+
+    /// Update filter with a linearized measurement model, given the Jacobian `H` and the
+    /// residual `y_res = y - h(x)` of the nonlinear model evaluated at the current estimate.
+    pub(crate) fn update_linearized<const Ny: usize>(
+        &mut self,
+        H: &SMatrix<F, Ny, Nx>,     // Measurement Jacobian
+        R: &SMatrix<F, Ny, Ny>,     // Covariance
+        y_res: &SMatrix<F, Ny, 1>,  // Measurement residual
+    ) -> Result<(), KalmanError> {
+        let P = self.covariance();
+        let S = H * P * H.transpose() + R * self.r_inflation;
+        let Some(Sinv) = S.try_inverse() else {
+            self.rejected += 1;
+            return Err(KalmanError::SingularInnovation);
+        };
+        let K = P * H.transpose() * Sinv;
+        self.influence = (K * H).trace() / nalgebra::convert(Nx as f64);
+        self.correct(&K, H, None, y_res);
+
+        #[cfg(feature = "trace")]
+        self.trace_update(y_res, &S, &K);
+        Ok(())
+    }
+
+    /// Update filter with new measurements, correcting only the states where `mask` is `true`.
+    /// The gain rows of masked-out states are zeroed, so these states are left unchanged by
+    /// the measurement even if they are correlated with the measured states.
+    pub fn update_with_state_mask<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+        mask: &[bool; Nx],      // States allowed to be corrected
+    ) -> Result<(), KalmanError> {
+        let (y_res, S) = self.innovation(C, R, y);
+        let Some(Sinv) = S.try_inverse() else {
+            self.rejected += 1;
+            return Err(KalmanError::SingularInnovation);
+        };
+
+        // Only correct the unmasked states
+        let mut K = self.gain(C, &Sinv);
+        for (i, &updatable) in mask.iter().enumerate() {
+            if !updatable {
+                K.row_mut(i).fill(F::zero());
+            }
+        }
+        self.influence = (K * C).trace() / nalgebra::convert(Nx as f64);
+
+        // Joseph form, as the gain is not optimal
+        let M = self.noise_cross_covariance::<Ny>();
+        self.correct_joseph(&K, C, &(R * self.r_inflation), M.as_ref(), &y_res);
+
+        #[cfg(feature = "trace")]
+        self.trace_update(&y_res, &S, &K);
+        Ok(())
+    }
+
+    /// Correct the state with a supplied gain `K` as `x = x + K*(y - C*x)`, leaving the covariance
+    /// untouched. This is the constant-gain (Luenberger) observer mode, such as for a fast inner
+    /// loop with the steady-state gain of `.steady_state_covariance`.
+    pub fn update_state_only<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        K: &SMatrix<F, Nx, Ny>, // Gain
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) {
+        let x = self.get_state();
+        self.steps_without_update = 0;
+        self.post = Some(VecMat {
+            x: self.constrain(x + K * (y - C * x), &self.covariance()),
+            P: self.covariance(),
+        });
+    }
+
+    /// Update filter with new measurements using a supplied constant gain `K`, such as from
+    /// `.compute_steady_state_gain`, skipping the inverse of `S`. The covariance is updated as
+    /// `(I - K*C) * P`, which is exact for the optimal gain. Use `.update_state_only` to leave
+    /// the covariance untouched.
+    pub fn update_with_gain<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+        K: &SMatrix<F, Nx, Ny>, // Gain
+    ) {
+        let y_res = y - C * self.get_state();
+        self.influence = (K * C).trace() / nalgebra::convert(Nx as f64);
+        self.correct(K, C, None, &y_res);
+    }
+
+    /// Update filter with new measurements, unless the measurement fails a chi-squared gate.
+    /// The measurement is applied only if its normalized innovation squared (squared Mahalanobis
+    /// distance) `y_res^T * S^-1 * y_res` is at most `threshold`. As `S` includes the current
+    /// covariance, the gate widens along with the uncertainty, such as after long prediction gaps.
+    /// A suitable `threshold` is a chi-squared quantile for `Ny` degrees of freedom.
+    ///
+    /// Returns `true` if the measurement was accepted and `false` if it was rejected.
+    pub fn update_gated<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+        threshold: F,
+    ) -> bool {
+        let (y_res, S) = self.innovation(C, R, y);
+        let Some(Sinv) = S.try_inverse() else {
+            self.rejected += 1;
+            return false;
+        };
+        if (y_res.transpose() * Sinv * y_res)[0] > threshold {
+            self.rejected += 1;
+            return false;
+        }
+        self.update(C, R, y).is_ok()
+    }
+
+    /// Get the measurement the current estimate predicts, `C*x`, such as for logging
+    /// expected against actual sensor readings.
+    pub fn predict_measurement<const Ny: usize>(&self, C: &SMatrix<F, Ny, Nx>) -> SMatrix<F, Ny, 1> {
+        C * self.get_state()
+    }
+
+    /// Get the measurement residual (innovation) `y - C*x` against the current estimate.
+    pub fn measurement_residual<const Ny: usize>(
+        &self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> SMatrix<F, Ny, 1> {
+        y - self.predict_measurement(C)
+    }
+
+    /// Get the standardized innovation of the last update made with `.update`, `.update_joseph`
+    /// or the updates built on them, which is each residual component divided by its predicted
+    /// standard deviation `sqrt(S_ii)`, giving the z-scores of the fused measurement. Returns
+    /// `None` before the first update, if the last update did not have `Ny` components, or if
+    /// it had more than `Nx` components.
+    pub fn standardized_innovation<const Ny: usize>(&self) -> Option<SMatrix<F, Ny, 1>> {
+        let (z, n) = self.last_standardized_innovation.as_ref()?;
+        (*n == Ny).then(|| z.fixed_view::<Ny, 1>(0, 0).into_owned())
+    }
+
+    /// Get the standardized innovation of a measurement against the current estimate,
+    /// as would be reported by `.standardized_innovation` after updating with it.
+    pub(crate) fn standardize_innovation<const Ny: usize>(
+        &self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> SMatrix<F, Ny, 1> {
+        let (y_res, S) = self.innovation(C, R, y);
+        SMatrix::from_fn(|i, _| y_res[i] / S[(i, i)].sqrt())
+    }
+
+    /// Get the normalized innovation squared (NIS) `y_res^T * S^-1 * y_res` of a measurement
+    /// against the current estimate. Call before `.update`; for a consistent filter the NIS
+    /// averages to `Ny` over many steps. Returns `None` if `S` is not invertible.
+    pub fn normalized_innovation_squared<const Ny: usize>(
+        &self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> Option<F> {
+        let (y_res, S) = self.innovation(C, R, y);
+        Some((y_res.transpose() * S.try_inverse()? * y_res)[0])
+    }
+
+    /// Get the Mahalanobis distance `sqrt(y_res^T * S^-1 * y_res)` of a measurement from the
+    /// predicted measurement of the current estimate, such as for associating measurements with
+    /// tracks. Returns `None` if `S` is not invertible.
+    pub fn mahalanobis_distance<const Ny: usize>(
+        &self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> Option<F> {
+        Some(self.normalized_innovation_squared(C, R, y)?.max(F::zero()).sqrt())
+    }
+
+    /// Get the normalized estimation error squared (NEES) `(x - x_true)^T * P^-1 * (x - x_true)`
+    /// of the current estimate against the true state, such as in simulation. For a consistent
+    /// filter the NEES averages to `Nx` over many steps. Returns `None` if `P` is not invertible.
+    pub fn normalized_estimation_error_squared(&self, x_true: &SMatrix<F, Nx, 1>) -> Option<F> {
+        let error = self.get_state() - x_true;
+        Some((error.transpose() * self.covariance().try_inverse()? * error)[0])
+    }
+
+    /// Get the posterior `(x, P)` the filter would have after fusing measurement `y` with `.update`,
+    /// without modifying the filter. The state is constrained by `.set_equality_constraint` and
+    /// `.set_state_bounds` as in `.update`. Returns `None` if the innovation covariance is not invertible.
+    pub fn hypothetical_posterior<const Ny: usize>(
+        &self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> Option<(SMatrix<F, Nx, 1>, SMatrix<F, Nx, Nx>)> {
+        let (y_res, S) = self.innovation(C, R, y);
+        let P = self.covariance();
+        let K = self.gain(C, &S.try_inverse()?);
+        let P = match self.noise_cross_covariance::<Ny>() {
+            Some(M) => P - K * (C * P + M.transpose()),
+            None => (SMatrix::identity() - K * C) * P,
+        };
+        let x = self.constrain(self.get_state() + K * y_res, &P);
+        Some((x, (P + P.transpose()).scale(nalgebra::convert(0.5))))
+    }
+
+    /// Get the mean and covariance `(x, P)` predicted `steps` steps ahead of the current
+    /// estimate with constant input `u` and the nominal `Q`, without modifying the filter.
+    pub fn predict_n(&self, steps: usize, u: &SMatrix<F, Nu, 1>) -> (SMatrix<F, Nx, 1>, SMatrix<F, Nx, Nx>) {
+        self.forecast_iter(u)
+            .take(steps)
+            .last()
+            .unwrap_or((self.get_state(), self.covariance()))
+    }
+
+    /// Forecast the mean and covariance `(x, P)` `steps` steps ahead of the current estimate with
+    /// constant input `u`, without modifying the filter, such as for planning. Same as `.predict_n`.
+    pub fn forecast(&self, steps: usize, u: &SMatrix<F, Nu, 1>) -> (SMatrix<F, Nx, 1>, SMatrix<F, Nx, Nx>) {
+        self.predict_n(steps, u)
+    }
+
+    /// Get the covariance predicted `steps` steps ahead of the current estimate with constant
+    /// input `u` and the nominal `Q`, without modifying the filter. This is the covariance of `.predict_n`.
+    pub fn predicted_covariance_at(&self, steps: usize, u: &SMatrix<F, Nu, 1>) -> SMatrix<F, Nx, Nx> {
+        self.predict_n(steps, u).1
+    }
+
+    /// Get an iterator over the predicted mean and covariance `(x, P)` of each step ahead of
+    /// the current estimate, with constant input `u` and the nominal `Q`. The iterator never
+    /// ends, so limit it with e.g. `.take(steps)`. The filter is not modified.
+    pub fn forecast_iter(&self, u: &SMatrix<F, Nu, 1>) -> Forecast<'_, Nx, Nu, F> {
+        Forecast {
+            filter: self,
+            u: *u,
+            x: self.get_state(),
+            P: self.covariance(),
+        }
+    }
+
+    /// Simulate the covariance through a planned schedule of predictions and updates, purely in
+    /// covariance space as no measurement values are needed. The covariance after each step is
+    /// written to `out`, and the number of simulated steps, limited by the length of `out`,
+    /// is returned. Predictions use the same forgetting factor, effective `Q` and variance
+    /// ceiling as `.predict`. The filter is not modified.
+    pub fn simulate_covariance<const Ny: usize>(
+        &self,
+        schedule: &[CovarianceStep<'_, Nx, Ny, F>],
+        out: &mut [SMatrix<F, Nx, Nx>],
+    ) -> usize {
+        let mut filter = self.clone();
+        let mut P = self.covariance();
+        for (step, out) in schedule.iter().zip(out.iter_mut()) {
+            P = match step {
+                CovarianceStep::Predict => {
+                    let P = filter.saturate_variances(filter.inflate_covariance(self.A * P * self.A.transpose()));
+                    filter.decay_q_inflation();
+                    P
+                }
+                CovarianceStep::Update { C, R } => {
+                    let S = *C * P * C.transpose() + *R * self.r_inflation;
+                    match S.try_inverse() {
+                        Some(Sinv) => (SMatrix::identity() - P * C.transpose() * Sinv * *C) * P,
+                        None => P,
+                    }
+                }
+            };
+            *out = P;
+        }
+        schedule.len().min(out.len())
+    }
+
+    /// Get the predicted mean and per-state standard deviations `(x, sqrt(diag(P)))` of each
+    /// step ahead of the current estimate, with constant input `u` and the nominal `Q`, such as
+    /// for plotting a forecast with its RMSE envelope. The forecast is written to `out` with one
+    /// entry per step up to the horizon given by its length. The filter is not modified.
+    pub fn forecast_envelope(
+        &self,
+        u: &SMatrix<F, Nu, 1>,
+        out: &mut [(SMatrix<F, Nx, 1>, SMatrix<F, Nx, 1>)],
+    ) {
+        for ((x, P), out) in self.forecast_iter(u).zip(out.iter_mut()) {
+            *out = (x, P.diagonal().map(|p| p.sqrt()));
+        }
+    }
+
+    /// Get the number of prediction steps with constant input `u`, without measurements, until
+    /// the variance of the state at `state_index` exceeds `threshold`. The filter is not modified.
+    /// Returns `None` if the variance does not exceed the threshold within `max_steps` steps.
+    pub fn steps_until_variance_exceeds(
+        &self,
+        state_index: usize,
+        threshold: F,
+        u: &SMatrix<F, Nu, 1>,
+        max_steps: usize,
+    ) -> Option<usize> {
+        self.forecast_iter(u)
+            .take(max_steps)
+            .position(|(_, P)| P[(state_index, state_index)] > threshold)
+            .map(|steps| steps + 1)
+    }
+
+    /// Get an orthonormal basis of the unobservable subspace of the model with output matrix `C`,
+    /// which is the null space of the observability matrix `[C; C*A; ...; C*A^(Nx-1)]`.
+    /// Returns the dimension `n` of the subspace together with a matrix whose first `n` columns
+    /// form the basis, while the remaining columns span the observable subspace.
+    pub fn unobservable_subspace<const Ny: usize>(&self, C: &SMatrix<F, Ny, Nx>) -> (usize, SMatrix<F, Nx, Nx>) {
+        // Observability Gramian `O^T * O`, sharing the null space of `O`
+        let mut gramian = SMatrix::<F, Nx, Nx>::zeros();
+        let mut CA = *C;
+        for _ in 0..Nx {
+            gramian += CA.transpose() * CA;
+            CA *= self.A;
+        }
+
+        let (eigenvalues, V) = linalg::symmetric_eigen(&gramian);
+        let max = eigenvalues.iter().fold(F::zero(), |acc, e| acc.max(e.abs()));
+        let tol = F::default_epsilon().sqrt() * max;
+
+        // Order the unobservable directions first
+        let unobservable = |i: &usize| eigenvalues[*i].abs() <= tol;
+        let order = (0..Nx).filter(unobservable).chain((0..Nx).filter(|i| !unobservable(i)));
+        let mut basis = SMatrix::<F, Nx, Nx>::zeros();
+        for (column, i) in order.enumerate() {
+            basis.set_column(column, &V.column(i));
+        }
+        ((0..Nx).filter(unobservable).count(), basis)
+    }
+
+    /// Get the steady-state prior covariance for repeated predictions and updates with the
+    /// measurement model `C` and `R`, by iterating the Riccati recursion from the current
+    /// covariance until it changes by less than `tol` (Frobenius norm) between iterations.
+    /// The recursion uses the same effective noise as `.predict` and `.update`, with `R` scaled by
+    /// the measurement inflation and `Q` by the current inflation and adaptive scale, along with
+    /// the forgetting factor. Returns `None` if it did not converge within `max_iter` iterations.
+    pub fn steady_state_covariance<const Ny: usize>(
+        &self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        tol: F,
+        max_iter: usize,
+    ) -> Option<SMatrix<F, Nx, Nx>> {
+        let R = R * self.r_inflation;
+        let mut P = self.covariance();
+        for _ in 0..max_iter {
+            let S = C * P * C.transpose() + R;
+            let K = P * C.transpose() * S.try_inverse()?;
+            let P_post = (SMatrix::identity() - K * C) * P;
+            let P_next = self.inflate_covariance(self.A * P_post * self.A.transpose());
+            let P_next = (P_next + P_next.transpose()).scale(nalgebra::convert(0.5));
+            if (P_next - P).norm() < tol {
+                return Some(P_next);
+            }
+            P = P_next;
+        }
+        None
+    }
+
+    /// Get the steady-state Kalman gain `K = P*C^T * (C*P*C^T + R)^-1` for the measurement model
+    /// `C` and `R`, with `P` the steady-state prior covariance of `.steady_state_covariance`
+    /// and `R` scaled by the measurement inflation.
+    /// Returns `None` if the covariance did not converge.
+    pub fn compute_steady_state_gain<const Ny: usize>(
+        &self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        tol: F,
+        max_iter: usize,
+    ) -> Option<SMatrix<F, Nx, Ny>> {
+        let P = self.steady_state_covariance(C, R, tol, max_iter)?;
+        Some(P * C.transpose() * (C * P * C.transpose() + R * self.r_inflation).try_inverse()?)
+    }
+
+    /// Initialize the estimate from a first measurement, setting the state to the weighted
+    /// least-squares solution of `C*x = y` and the covariance to `(C^T * R^-1 * C)^-1`. State
+    /// directions not observed by the measurement are set to zero with `unobserved_variance`.
+    /// Returns `false`, leaving the filter unchanged, if `R` is not invertible.
+    pub fn initialize_from_measurement<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+        unobserved_variance: F,
+    ) -> bool {
+        let Some(Rinv) = R.try_inverse() else { return false };
+        let (eigenvalues, V) = linalg::symmetric_eigen(&(C.transpose() * Rinv * C));
+        let max = eigenvalues.iter().fold(F::zero(), |acc, e| acc.max(e.abs()));
+        let tol = F::default_epsilon().sqrt() * max;
+
+        // Invert the information in the observed directions only
+        let observed = eigenvalues.map(|e| e > tol);
+        let inverse = SMatrix::from_fn(|i, _| if observed[i] { F::one() / eigenvalues[i] } else { F::zero() });
+        let variance = SMatrix::from_fn(|i, _| if observed[i] { inverse[i] } else { unobserved_variance });
+
+        self.prio = VecMat {
+            x: V * SMatrix::from_diagonal(&inverse) * V.transpose() * C.transpose() * Rinv * y,
+            P: V * SMatrix::from_diagonal(&variance) * V.transpose(),
+        };
+        self.post = None;
+        true
+    }
+
+    /// Get the steady-state innovation covariance `C*P*C^T + R` for repeated predictions and
+    /// updates with the measurement model `C` and `R`, with `P` the steady-state prior covariance
+    /// and `R` scaled by the measurement inflation, such as for precomputing fault detection thresholds. See `.steady_state_covariance` for
+    /// the convergence parameters. Returns `None` if the covariance did not converge.
+    pub fn steady_state_innovation_covariance<const Ny: usize>(
+        &self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        tol: F,
+        max_iter: usize,
+    ) -> Option<SMatrix<F, Ny, Ny>> {
+        let P = self.steady_state_covariance(C, R, tol, max_iter)?;
+        Some(C * P * C.transpose() + R * self.r_inflation)
+    }
+
+    /// Reset the state to `x` and the covariance to the steady-state prior covariance for the
+    /// measurement model `C` and `R`, such as after reacquiring a lost track. See
+    /// `.steady_state_covariance` for the convergence parameters. Returns `false`, leaving the
+    /// filter unchanged, if the covariance did not converge.
+    pub fn reset_to_steady_state<const Ny: usize>(
+        &mut self,
+        x: SMatrix<F, Nx, 1>,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        tol: F,
+        max_iter: usize,
+    ) -> bool {
+        let Some(P) = self.steady_state_covariance(C, R, tol, max_iter) else { return false };
+        self.prio = VecMat { x, P };
+        self.post = None;
+        true
+    }
+
+    /// Export a fixed-gain estimator using the steady-state Kalman gain for the measurement
+    /// model `C` and `R`, starting from the current state. See `.steady_state_covariance`
+    /// for the convergence parameters. Returns `None` if the covariance did not converge.
+    pub fn export_fixed_gain_estimator<const Ny: usize>(
+        &self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        tol: F,
+        max_iter: usize,
+    ) -> Option<FixedGainEstimator<Nx, Nu, Ny, F>> {
+        let P = self.steady_state_covariance(C, R, tol, max_iter)?;
+        let K = P * C.transpose() * (C * P * C.transpose() + R * self.r_inflation).try_inverse()?;
+        Some(FixedGainEstimator::new(self.A, self.B, *C, K, self.get_state()))
+    }
+
+    /// Fast-forward the filter past its initialization transient by repeatedly applying the
+    /// representative measurement `y` followed by a prediction, until the posterior covariance
+    /// changes by less than `tol` (Frobenius norm) between iterations.
+    ///
+    /// Returns the settled posterior `(x, P)`, leaving the filter in that posterior state,
+    /// or `None` if the covariance did not settle within `max_iter` iterations or an update failed.
+    pub fn settle<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+        tol: F,
+        max_iter: usize,
+    ) -> Option<(SMatrix<F, Nx, 1>, SMatrix<F, Nx, Nx>)> {
+        let mut last: Option<SMatrix<F, Nx, Nx>> = None;
+        for _ in 0..max_iter {
+            self.update(C, R, y).ok()?;
+            let P = self.covariance();
+            if last.is_some_and(|last| (P - last).norm() < tol) {
+                return Some((self.get_state(), P));
+            }
+            last = Some(P);
+            self.predict();
+        }
+        None
+    }
+
+    /// Get the Gaussian log-likelihood of a measurement against the current estimate,
+    /// `-0.5 * (Ny*ln(2*pi) + ln(det(S)) + y_res^T * S^-1 * y_res)`, such as for comparing
+    /// filter hypotheses or validating sensors. Returns `None` if `S` is not positive-definite.
+    pub fn measurement_log_likelihood<const Ny: usize>(
+        &self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> Option<F> {
+        let (y_res, S) = self.innovation(C, R, y);
+        let chol = S.cholesky()?;
+        let log_det = chol.l_dirty().diagonal().iter().fold(F::zero(), |acc, l| acc + l.ln());
+        let mahalanobis = (y_res.transpose() * chol.solve(&y_res))[0];
+        let ny: F = nalgebra::convert(Ny as f64);
+        let half: F = nalgebra::convert(0.5);
+        Some(-(ny * F::two_pi().ln() * half + log_det + mahalanobis * half))
+    }
+
+    /// Get the total log-likelihood of a recorded sequence of measurements, running a clone
+    /// of the filter over the data. Each measurement is scored against and fused into the
+    /// current estimate, followed by a prediction with the corresponding input. The filter
+    /// itself is not modified. Returns `None` if the number of inputs differs from the number of
+    /// measurements, or if any innovation covariance is not positive-definite.
+    pub fn batch_log_likelihood<const Ny: usize>(
+        &self,
+        C: &SMatrix<F, Ny, Nx>,               // Output matrix
+        R: &SMatrix<F, Ny, Ny>,               // Covariance
+        inputs: &[SMatrix<F, Nu, 1>],         // Input of each step
+        measurements: &[SMatrix<F, Ny, 1>],   // Measurement of each step
+    ) -> Option<F> {
+        if inputs.len() != measurements.len() {
+            return None;
+        }
+        let mut filter = self.clone();
+        let mut total = F::zero();
+        for (u, y) in inputs.iter().zip(measurements) {
+            total += filter.measurement_log_likelihood(C, R, y)?;
+            filter.update(C, R, y).ok()?;
+            filter.predict_with_input(*u);
+        }
+        Some(total)
+    }
+
+    /// Get the expected log-likelihood of a future measurement with the measurement model `C`
+    /// and `R` under its predictive distribution, `-0.5 * (Ny*ln(2*pi) + ln(det(S)) + Ny)`,
+    /// which is the negative entropy of the predictive distribution. Sharper measurements have
+    /// higher expected log-likelihoods. Returns `None` if `S` is not positive-definite.
+    pub fn expected_log_likelihood<const Ny: usize>(
+        &self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+    ) -> Option<F> {
+        let (_, S) = self.innovation(C, R, &SMatrix::zeros());
+        let L = S.cholesky()?.unpack();
+        let log_det = L.diagonal().iter().fold(F::zero(), |acc, l| acc + l.ln());
+        let ny: F = nalgebra::convert(Ny as f64);
+        let half: F = nalgebra::convert(0.5);
+        Some(-(ny * (F::two_pi().ln() + F::one()) * half + log_det))
+    }
+
+    /// Get state vector `x`.
+    /// Returns the posterior estimate if it exists, otherwise returns priori prediction.
+    pub fn get_state(&self) -> SMatrix<F, Nx, 1> {
+        self.post.as_ref().unwrap_or(&self.prio).x
+    }
+
+    /// Get covariance matrix `P`.
+    /// Returns the posterior covariance if it exists, otherwise returns priori covariance.
+    /// The posterior covariance is complete and symmetric immediately after each `.update`.
+    pub fn get_covariance(&self) -> SMatrix<F, Nx, Nx> {
+        self.covariance()
+    }
+
+    /// Get the trace of the covariance of the current estimate, the sum of the state variances,
+    /// such as for logging or detecting divergence.
+    pub fn covariance_trace(&self) -> F {
+        self.covariance().trace()
+    }
+
+    /// Get the determinant of the covariance of the current estimate, the generalized variance,
+    /// such as for detecting over-confidence. Computed from the Cholesky factor, or from the
+    /// eigenvalues if the covariance is only semi-definite.
+    pub fn covariance_determinant(&self) -> F {
+        let P = self.covariance();
+        match P.cholesky() {
+            Some(chol) => chol.l_dirty().diagonal().iter().fold(F::one(), |acc, &l| acc * l * l),
+            None => linalg::symmetric_eigen(&P).0.iter().fold(F::one(), |acc, &e| acc * e),
+        }
+    }
+
+    /// Get the condition number of the covariance of the current estimate, the ratio of its
+    /// largest to smallest eigenvalue. A growing condition number warns that the filter is
+    /// becoming numerically fragile, such that e.g. the square-root filter in `kalman_sqrt` may be
+    /// needed. Returns infinity if the covariance is not positive-definite.
+    pub fn covariance_condition_number(&self) -> F {
+        let (eigenvalues, _) = linalg::symmetric_eigen(&self.covariance());
+        let (min, max) = (eigenvalues.min(), eigenvalues.max());
+        if min > F::zero() { max / min } else { F::one() / F::zero() }
+    }
+
+    /// Check whether the covariance of the current estimate is positive-definite, by attempting
+    /// its Cholesky decomposition.
+    pub fn is_positive_definite(&self) -> bool {
+        self.covariance().cholesky().is_some()
+    }
+
+    /// Get the time of the current estimate, accumulated from the time step of each prediction.
+    pub fn get_time(&self) -> F {
+        self.time
+    }
+
+    /// Get state vector `x` together with the time it is valid at.
+    pub fn get_state_with_time(&self) -> (F, SMatrix<F, Nx, 1>) {
+        (self.time, self.get_state())
+    }
+
+    /// Get the influence of the latest measurement, `trace(K*C) / Nx`. This is the fraction
+    /// of the state replaced by the measurement, ranging from zero for an uninformative
+    /// measurement to one for a perfect measurement of all states.
+    pub fn measurement_influence(&self) -> F {
+        self.influence
+    }
+
+    /// Get the number of consecutive predictions since a measurement was last accepted,
+    /// counting measurements that are absent or rejected by e.g. `.update_gated` alike.
+    pub fn consecutive_no_update(&self) -> usize {
+        self.steps_without_update
+    }
+
+    /// Get the number of measurements rejected since creation or `.reset_rejected_count`, by
+    /// `.update_gated`, by each rejected component of `.update_gated_components`, or by any
+    /// update failing on a singular innovation covariance.
+    pub fn rejected_count(&self) -> usize {
+        self.rejected
+    }
+
+    /// Restart the count of rejected measurements from zero.
+    pub fn reset_rejected_count(&mut self) {
+        self.rejected = 0;
+    }
+
+    /// Check whether the filter is effectively running in open loop (dead-reckoning),
+    /// having gone at least `threshold` consecutive predictions without an accepted measurement.
+    pub fn is_open_loop(&self, threshold: usize) -> bool {
+        self.steps_without_update >= threshold
+    }
+
+    /// Get the accumulated Frobenius norm of the Kalman gains `K` of all updates, which is the
+    /// sensitivity of the estimate to the measurements. See `SensitivityWindow` for a windowed view.
+    pub fn cumulative_gain_norm(&self) -> F {
+        self.cumulative_gain_norm
+    }
+
+    /// Get the standard deviation `sqrt(P_ii)` of each state.
+    pub fn get_standard_deviations(&self) -> SMatrix<F, Nx, 1> {
+        self.covariance().diagonal().map(|p| p.sqrt())
+    }
+
+    /// Get the variance `a^T * P * a` of the linear combination of states `a^T * x`.
+    pub fn linear_combination_variance(&self, a: &SMatrix<F, Nx, 1>) -> F {
+        (a.transpose() * self.covariance() * a)[0]
+    }
+
+    /// Get the cross-covariance block of `P` between the states at `indices_a` and `indices_b`,
+    /// such that element `(i, j)` is the covariance of states `indices_a[i]` and `indices_b[j]`.
+    pub fn cross_covariance<const Na: usize, const Nb: usize>(
+        &self,
+        indices_a: &[usize; Na],
+        indices_b: &[usize; Nb],
+    ) -> SMatrix<F, Na, Nb> {
+        let P = self.covariance();
+        SMatrix::from_fn(|i, j| P[(indices_a[i], indices_b[j])])
+    }
+
+    /// Get the correlation matrix of the state, with elements `P_ij / sqrt(P_ii * P_jj)`.
+    pub fn get_correlation_matrix(&self) -> SMatrix<F, Nx, Nx> {
+        let P = self.covariance();
+        SMatrix::from_fn(|i, j| P[(i, j)] / (P[(i, i)] * P[(j, j)]).sqrt())
+    }
+
+    /// Get the current estimate in information form, as the information vector `P^-1 * x` and
+    /// the information matrix `P^-1`. Returns `None` if the covariance is not positive-definite.
+    pub fn to_information_form(&self) -> Option<(SMatrix<F, Nx, 1>, SMatrix<F, Nx, Nx>)> {
+        let information = self.covariance().cholesky()?.inverse();
+        Some((information * self.get_state(), information))
+    }
+
+    /// Merge the estimate of this filter with the estimate of `other`, such as two tracks of
+    /// the same object, by fusing them in information form as a product of Gaussians. The
+    /// estimates are assumed independent. Returns a copy of this filter with the merged estimate,
+    /// or `None` if either covariance is not positive-definite.
+    pub fn merge_with(&self, other: &Self) -> Option<Self> {
+        let (y_a, Y_a) = self.to_information_form()?;
+        let (y_b, Y_b) = other.to_information_form()?;
+        let mut merged = self.clone();
+        merged.set_information_form(&(y_a + y_b), &(Y_a + Y_b)).then_some(merged)
+    }
+
+    /// Get the Fisher information `C^T * R^-1 * C` of a measurement with the measurement model
+    /// `C` and `R`. Fusing the measurement adds this to the information matrix `P^-1` of the
+    /// estimate. Returns `None` if `R` is not invertible.
+    pub fn fisher_information<const Ny: usize>(
+        &self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+    ) -> Option<SMatrix<F, Nx, Nx>> {
+        let Rinv = (R * self.r_inflation).try_inverse()?;
+        Some(C.transpose() * Rinv * C)
+    }
+
+    /// Set the current estimate from information form, given the information vector `P^-1 * x`
+    /// and the information matrix `P^-1`. Returns `false`, leaving the filter unchanged, if the
+    /// information matrix is not positive-definite.
+    pub fn set_information_form(
+        &mut self,
+        information_vector: &SMatrix<F, Nx, 1>,
+        information_matrix: &SMatrix<F, Nx, Nx>,
+    ) -> bool {
+        let Some(chol) = information_matrix.cholesky() else { return false };
+        self.prio = VecMat {
+            x: chol.solve(information_vector),
+            P: chol.inverse(),
+        };
+        self.post = None;
+        true
+    }
+
+    /// Get the KL divergence from the prior to the posterior Gaussian of the updates since the
+    /// last prediction, `0.5 * (tr(P0^-1 * P1) + d^T * P0^-1 * d - Nx + ln(det(P0) / det(P1)))`
+    /// with `d = x1 - x0`, which is the information gained from the measurements. Large values
+    /// flag surprising measurements. Returns `None` if no update has been made since the last
+    /// prediction, or if either covariance is not positive-definite.
+    pub fn update_kl_divergence(&self) -> Option<F> {
+        let post = self.post.as_ref()?;
+        let prio = self.prio.P.cholesky()?;
+        let post_chol = post.P.cholesky()?;
+
+        // Half the log-determinants, from the diagonals of the Cholesky factors
+        let half_log_det = |L: &SMatrix<F, Nx, Nx>| L.diagonal().iter().fold(F::zero(), |acc, l| acc + l.ln());
+        let log_ratio = half_log_det(prio.l_dirty()) - half_log_det(post_chol.l_dirty());
+
+        let d = post.x - self.prio.x;
+        let trace = prio.solve(&post.P).trace();
+        let mahalanobis = (d.transpose() * prio.solve(&d))[0];
+        let half: F = nalgebra::convert(0.5);
+        Some(half * (trace + mahalanobis - nalgebra::convert(Nx as f64)) + log_ratio)
+    }
+
+    /// Get the natural logarithm of the determinant of the covariance matrix `P`.
+    /// Returns `None` if the covariance is not positive-definite.
+    pub fn covariance_log_determinant(&self) -> Option<F> {
+        let L = self.covariance().cholesky()?.unpack();
+        Some(L.diagonal().iter().fold(F::zero(), |acc, l| acc + l.ln()) * nalgebra::convert(2.0))
+    }
+
+    /// Apply the correction of the optimal gain `K` to the posterior estimate.
+    fn correct<const Ny: usize>(
+        &mut self,
+        K: &SMatrix<F, Nx, Ny>,
+        C: &SMatrix<F, Ny, Nx>,
+        M: Option<&SMatrix<F, Nx, Ny>>,
+        y_res: &SMatrix<F, Ny, 1>,
+    ) {
+        let P = self.covariance();
+        let P = match M {
+            Some(M) => P - K * (C * P + M.transpose()),
+            None => (SMatrix::identity() - K * C) * P,
+        };
+        self.set_posterior(K, y_res, P);
+    }
+
+    /// Apply the correction of gain `K` to the posterior estimate with the Joseph form of the
+    /// covariance update, which is valid for any gain.
+    fn correct_joseph<const Ny: usize>(
+        &mut self,
+        K: &SMatrix<F, Nx, Ny>,
+        C: &SMatrix<F, Ny, Nx>,
+        R: &SMatrix<F, Ny, Ny>,
+        M: Option<&SMatrix<F, Nx, Ny>>,
+        y_res: &SMatrix<F, Ny, 1>,
+    ) {
+        let IKC = SMatrix::<F, Nx, Nx>::identity() - K * C;
+        let mut P = IKC * self.covariance() * IKC.transpose() + K * R * K.transpose();
+        if let Some(M) = M {
+            P -= IKC * M * K.transpose() + K * M.transpose() * IKC.transpose();
+        }
+        self.set_posterior(K, y_res, P);
+    }
+
+    /// Set the posterior estimate after a correction of gain `K` resulting in covariance `P`.
+    fn set_posterior<const Ny: usize>(
+        &mut self,
+        K: &SMatrix<F, Nx, Ny>,
+        y_res: &SMatrix<F, Ny, 1>,
+        P: SMatrix<F, Nx, Nx>,
+    ) {
+        self.steps_without_update = 0;
+        self.cumulative_gain_norm += K.norm();
+
+        // Updated (a posteriori) estimate and symmetrized covariance,
+        // building on any earlier posterior since the last prediction
+        self.post = Some(VecMat {
+            x: self.constrain(self.get_state() + K * y_res, &P),
+            P: (P + P.transpose()).scale(nalgebra::convert(0.5)),
+        });
+    }
+
+    /// Project an updated mean `x` with covariance `P` onto the equality constraint,
+    /// and clamp it into the state bounds.
+    fn constrain(&self, x: SMatrix<F, Nx, 1>, P: &SMatrix<F, Nx, Nx>) -> SMatrix<F, Nx, 1> {
+        let x = match self.equality_constraint.as_ref() {
+            Some((D, d)) => {
+                let W = linalg::symmetric_pseudo_inverse(&(D * P * D.transpose()));
+                x - P * D.transpose() * W * (D * x - d)
+            }
+            None => x,
+        };
+        clamp(x, self.state_bounds.as_ref())
+    }
+
+    /// Measurement residual and innovation covariance `(y_res, S)` of a measurement
+    /// against the current estimate, with `S = C*P*C^T + R + C*M + M^T*C^T` for the noise
+    /// cross-covariance `M` of `.set_cross_covariance`.
+    fn innovation<const Ny: usize>(
+        &self,
+        C: &SMatrix<F, Ny, Nx>,
+        R: &SMatrix<F, Ny, Ny>,
+        y: &SMatrix<F, Ny, 1>,
+    ) -> (SMatrix<F, Ny, 1>, SMatrix<F, Ny, Ny>) {
+        let mut S = C * self.covariance() * C.transpose() + R * self.r_inflation;
+        if let Some(M) = self.noise_cross_covariance::<Ny>() {
+            S += C * M + M.transpose() * C.transpose();
+        }
+        (y - C * self.get_state(), S)
+    }
+
+    /// Optimal Kalman gain `K = (P*C^T + M) * S^-1` for the inverse innovation covariance
+    /// `Sinv` of `.innovation`.
+    fn gain<const Ny: usize>(&self, C: &SMatrix<F, Ny, Nx>, Sinv: &SMatrix<F, Ny, Ny>) -> SMatrix<F, Nx, Ny> {
+        let mut PCt = self.covariance() * C.transpose();
+        if let Some(M) = self.noise_cross_covariance::<Ny>() {
+            PCt += M;
+        }
+        PCt * Sinv
+    }
+
+    /// Inflate a propagated covariance `P` by the forgetting factor and add the process noise `Q`,
+    /// scaled by the innovation-triggered inflation and the adaptive scale.
+    fn inflate_covariance(&self, P: SMatrix<F, Nx, Nx>) -> SMatrix<F, Nx, Nx> {
+        P * self.forgetting_factor + self.Q * self.q_inflation * self.adaptive_q_scale
+    }
+
+    /// Saturate the variances of a predicted covariance `P` at the variance ceiling, scaling the
+    /// covariances alike to keep `P` positive semi-definite.
+    fn saturate_variances(&self, P: SMatrix<F, Nx, Nx>) -> SMatrix<F, Nx, Nx> {
+        let Some(max) = self.variance_ceiling.as_ref() else { return P };
+        let scale: SMatrix<F, Nx, 1> = SMatrix::from_fn(|i, _| {
+            let variance = P[(i, i)];
+            if variance > max[i] { (max[i] / variance).sqrt() } else { F::one() }
+        });
+        SMatrix::from_fn(|i, j| P[(i, j)] * scale[i] * scale[j])
+    }
+
+    /// Decay the inflation of `Q` towards nominal after a prediction.
+    fn decay_q_inflation(&mut self) {
+        if let Some((_, decay)) = self.q_inflation_trigger {
+            self.q_inflation = F::one() + (self.q_inflation - F::one()) * decay;
+        }
+    }
+
+    /// Pass an update with residual `y_res`, innovation covariance `S` and gain `K`, with the
+    /// resulting posterior, to the trace callback.
+    #[cfg(feature = "trace")]
+    fn trace_update<const Ny: usize>(&self, y_res: &SMatrix<F, Ny, 1>, S: &SMatrix<F, Ny, Ny>, K: &SMatrix<F, Nx, Ny>) {
+        if let (Some(trace), Some(post)) = (self.trace, self.post.as_ref()) {
+            trace(&TraceEvent::Update {
+                y_res: y_res.as_slice(),
+                S: S.as_slice(),
+                K: K.as_slice(),
+                x: post.x.as_slice(),
+                P: post.P.as_slice(),
+            });
+        }
+    }
+
+    /// Get the cross-covariance set with `.set_cross_covariance`, if it applies to `Ny` measurements.
+    fn noise_cross_covariance<const Ny: usize>(&self) -> Option<SMatrix<F, Nx, Ny>> {
+        self.cross_covariance
+            .as_ref()
+            .filter(|(_, n)| *n == Ny)
+            .map(|(M, _)| M.fixed_view::<Nx, Ny>(0, 0).into_owned())
+    }
+
+    /// Get the prior estimate `(x, P)` of the last prediction, ignoring any updates since.
+    pub(crate) fn prior(&self) -> (SMatrix<F, Nx, 1>, SMatrix<F, Nx, Nx>) {
+        (self.prio.x, self.prio.P)
+    }
+
+    /// Get the model propagation matrix used by the next prediction.
+    pub(crate) fn transition(&self) -> &SMatrix<F, Nx, Nx> {
+        &self.A
+    }
+
+    /// Covariance of the current estimate.
+    /// Returns the posterior covariance if it exists, otherwise returns priori covariance.
+    fn covariance(&self) -> SMatrix<F, Nx, Nx> {
+        self.post.as_ref().unwrap_or(&self.prio).P
+    }
+}
+
+impl<const Nu: usize, F: RealField + Copy> KalmanFilter<2, Nu, F> {
+    /// Provide kalman filter with a constant-velocity model over the state `[position, velocity]`,
+    /// driven by continuous white-noise acceleration of power spectral density `q`. This gives
+    /// `A = [1 dt; 0 1]` and `Q = q * [dt^3/3 dt^2/2; dt^2/2 dt]`, with no input, a zero initial
+    /// state, an identity initial covariance and a time step of `dt`.
+    pub fn constant_velocity(dt: F, q: F) -> Self {
+        let two: F = nalgebra::convert(2.);
+        let three: F = nalgebra::convert(3.);
+        let (dt2, dt3) = (dt * dt, dt * dt * dt);
+
+        let A = SMatrix::<F, 2, 2>::new(
+            F::one(), dt,
+            F::zero(), F::one(),
+        );
+        let Q = SMatrix::<F, 2, 2>::new(
+            dt3 / three, dt2 / two,
+            dt2 / two, dt,
+        ) * q;
+        let mut filter = Self::new(A, None, Q, SMatrix::zeros(), SMatrix::identity());
+        filter.set_time_step(dt);
+        filter
+    }
+}
+
+impl<const Nu: usize, F: RealField + Copy> KalmanFilter<3, Nu, F> {
+    /// Provide kalman filter with a constant-acceleration model over the state
+    /// `[position, velocity, acceleration]`, driven by continuous white-noise jerk of power
+    /// spectral density `q`, with no input, a zero initial state, an identity initial covariance
+    /// and a time step of `dt`.
+    pub fn constant_acceleration(dt: F, q: F) -> Self {
+        let [two, three, six, eight, twenty]: [F; 5] = [2., 3., 6., 8., 20.].map(nalgebra::convert);
+        let (dt2, dt3) = (dt * dt, dt * dt * dt);
+        let (dt4, dt5) = (dt3 * dt, dt3 * dt2);
+
+        let A = SMatrix::<F, 3, 3>::new(
+            F::one(), dt, dt2 / two,
+            F::zero(), F::one(), dt,
+            F::zero(), F::zero(), F::one(),
+        );
+        let Q = SMatrix::<F, 3, 3>::new(
+            dt5 / twenty, dt4 / eight, dt3 / six,
+            dt4 / eight, dt3 / three, dt2 / two,
+            dt3 / six, dt2 / two, dt,
+        ) * q;
+        let mut filter = Self::new(A, None, Q, SMatrix::zeros(), SMatrix::identity());
+        filter.set_time_step(dt);
+        filter
+    }
+}
+
+/// Builder of a `KalmanFilter`, naming each of the model matrices and initial values.
+/// Unset values default to an identity `A`, a zero `B`, an identity `Q`, a zero initial
+/// state and an identity initial covariance.
+pub struct KalmanFilterBuilder<const Nx: usize, const Nu: usize, F: RealField + Copy> {
+    A: SMatrix<F, Nx, Nx>,
+    B: SMatrix<F, Nx, Nu>,
+    Q: SMatrix<F, Nx, Nx>,
+    x_init: SMatrix<F, Nx, 1>,
+    P_init: SMatrix<F, Nx, Nx>,
+}
+
+impl<const Nx: usize, const Nu: usize, F: RealField + Copy> Default for KalmanFilterBuilder<Nx, Nu, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const Nx: usize, const Nu: usize, F: RealField + Copy> KalmanFilterBuilder<Nx, Nu, F> {
+    /// Create a builder with all values at their defaults.
+    pub fn new() -> Self {
+        Self {
+            A: SMatrix::identity(),
+            B: SMatrix::zeros(),
+            Q: SMatrix::identity(),
+            x_init: SMatrix::zeros(),
+            P_init: SMatrix::identity(),
+        }
+    }
+
+    /// Set the model propagation matrix `A`.
+    pub fn transition(mut self, A: SMatrix<F, Nx, Nx>) -> Self {
+        self.A = A;
+        self
+    }
+
+    /// Set the input matrix `B`.
+    pub fn input(mut self, B: SMatrix<F, Nx, Nu>) -> Self {
+        self.B = B;
+        self
+    }
+
+    /// Set the model noise covariance matrix `Q`.
+    pub fn process_noise(mut self, Q: SMatrix<F, Nx, Nx>) -> Self {
+        self.Q = Q;
+        self
+    }
+
+    /// Set the initial state `x_init`.
+    pub fn initial_state(mut self, x_init: SMatrix<F, Nx, 1>) -> Self {
+        self.x_init = x_init;
+        self
+    }
+
+    /// Set the initial covariance `P_init`.
+    pub fn initial_covariance(mut self, P_init: SMatrix<F, Nx, Nx>) -> Self {
+        self.P_init = P_init;
+        self
+    }
+
+    /// Build the filter, as with `KalmanFilter::new`.
+    pub fn build(self) -> KalmanFilter<Nx, Nu, F> {
+        KalmanFilter::new(self.A, Some(self.B), self.Q, self.x_init, self.P_init)
+    }
+}
+
+/// Compact fixed-layout binary encoding of the filter, for persisting it in e.g. flash storage.
+/// The matrices `A`, `B`, `Q`, the state `x` and the covariance `P` are stored in that order,
+/// each in column-major order as little-endian floats. Configuration other than the model
+/// and the estimate is not stored.
+macro_rules! impl_bytes {
+    ($F:ty) => {
+        impl<const Nx: usize, const Nu: usize> KalmanFilter<Nx, Nu, $F> {
+            /// Length in bytes of the binary encoding, `(3*Nx*Nx + Nx*Nu + Nx) * size_of::<F>()`.
+            pub const fn encoded_len() -> usize {
+                (3 * Nx * Nx + Nx * Nu + Nx) * core::mem::size_of::<$F>()
+            }
+
+            /// Encode the model and the current estimate into `bytes`, returning the number of
+            /// bytes written, or `None` if `bytes` is shorter than `.encoded_len()`.
+            pub fn to_bytes(&self, bytes: &mut [u8]) -> Option<usize> {
+                let len = Self::encoded_len();
+                let x = self.get_state();
+                let P = self.covariance();
+                let values = self.A.iter().chain(self.B.iter()).chain(self.Q.iter()).chain(x.iter()).chain(P.iter());
+                let chunks = bytes.get_mut(..len)?.chunks_exact_mut(core::mem::size_of::<$F>());
+                for (chunk, value) in chunks.zip(values) {
+                    chunk.copy_from_slice(&value.to_le_bytes());
+                }
+                Some(len)
+            }
+
+            /// Decode a filter encoded with `.to_bytes`, or `None` if `bytes` is shorter than `.encoded_len()`.
+            pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+                let mut values = bytes
+                    .get(..Self::encoded_len())?
+                    .chunks_exact(core::mem::size_of::<$F>())
+                    .map(|chunk| <$F>::from_le_bytes(chunk.try_into().unwrap()));
+                let A = SMatrix::from_iterator(values.by_ref().take(Nx * Nx));
+                let B = SMatrix::from_iterator(values.by_ref().take(Nx * Nu));
+                let Q = SMatrix::from_iterator(values.by_ref().take(Nx * Nx));
+                let x = SMatrix::from_iterator(values.by_ref().take(Nx));
+                let P = SMatrix::from_iterator(values);
+                Some(Self::new(A, Some(B), Q, x, P))
+            }
+        }
+    };
+}
+
+impl_bytes!(f32);
+impl_bytes!(f64);
+
+/// Conversion of the filter between scalar types, such as from a prototype in `f64` to `f32`
+/// for deployment, converting the model, the estimate and all configuration element-wise.
+macro_rules! impl_cast {
+    ($From:ty, $To:ty) => {
+        impl<const Nx: usize, const Nu: usize> From<&KalmanFilter<Nx, Nu, $From>> for KalmanFilter<Nx, Nu, $To> {
+            fn from(filter: &KalmanFilter<Nx, Nu, $From>) -> Self {
+                let c = |v: $From| v as $To;
+                Self {
+                    A: filter.A.cast(),
+                    B: filter.B.cast(),
+                    Q: filter.Q.cast(),
+                    prio: VecMat {
+                        x: filter.prio.x.cast(),
+                        P: filter.prio.P.cast(),
+                    },
+                    post: filter.post.as_ref().map(|post| VecMat {
+                        x: post.x.cast(),
+                        P: post.P.cast(),
+                    }),
+                    dt: c(filter.dt),
+                    time: c(filter.time),
+                    continuous_model: filter.continuous_model.map(|(F, B, Q)| (F.cast(), B.cast(), Q.cast())),
+                    influence: c(filter.influence),
+                    cumulative_gain_norm: c(filter.cumulative_gain_norm),
+                    q_inflation: c(filter.q_inflation),
+                    r_inflation: c(filter.r_inflation),
+                    forgetting_factor: c(filter.forgetting_factor),
+                    q_inflation_trigger: filter.q_inflation_trigger.map(|(threshold, decay)| (c(threshold), c(decay))),
+                    adaptive_q: filter.adaptive_q.map(|(window, scale)| (window, c(scale))),
+                    adaptive_q_scale: c(filter.adaptive_q_scale),
+                    adaptive_q_window: (filter.adaptive_q_window.0, c(filter.adaptive_q_window.1)),
+                    validation: filter.validation.map(c),
+                    nis_time_constant: filter.nis_time_constant.map(c),
+                    smoothed_nis: filter.smoothed_nis.map(c),
+                    last_standardized_innovation: filter.last_standardized_innovation.map(|(z, Ny)| (z.cast(), Ny)),
+                    steps_without_update: filter.steps_without_update,
+                    rejected: filter.rejected,
+                    // Trace callbacks are specific to the scalar type
+                    #[cfg(feature = "trace")]
+                    trace: None,
+                    forecast_bounds: filter.forecast_bounds.map(|(min, max)| (min.cast(), max.cast())),
+                    state_bounds: filter.state_bounds.map(|(min, max)| (min.cast(), max.cast())),
+                    equality_constraint: filter.equality_constraint.map(|(D, d)| (D.cast(), d.cast())),
+                    cross_covariance: filter.cross_covariance.map(|(M, Ny)| (M.cast(), Ny)),
+                    variance_ceiling: filter.variance_ceiling.map(|max| max.cast()),
+                    accumulated: filter.accumulated.map(|(i, I)| (i.cast(), I.cast())),
+                }
+            }
+        }
+
+        impl<const Nx: usize, const Nu: usize> From<KalmanFilter<Nx, Nu, $From>> for KalmanFilter<Nx, Nu, $To> {
+            fn from(filter: KalmanFilter<Nx, Nu, $From>) -> Self {
+                Self::from(&filter)
+            }
+        }
+    };
+}
+
+impl_cast!(f64, f32);
+impl_cast!(f32, f64);
+
+/// Iterator over the predicted mean and covariance of each step of a forecast,
+/// created by `KalmanFilter::forecast_iter`.
+pub struct Forecast<'a, const Nx: usize, const Nu: usize, F: RealField + Copy> {
+    filter: &'a KalmanFilter<Nx, Nu, F>,
+    u: SMatrix<F, Nu, 1>,
+    x: SMatrix<F, Nx, 1>,
+    P: SMatrix<F, Nx, Nx>,
+}
+
+impl<const Nx: usize, const Nu: usize, F: RealField + Copy> Iterator for Forecast<'_, Nx, Nu, F> {
+    type Item = (SMatrix<F, Nx, 1>, SMatrix<F, Nx, Nx>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let filter = self.filter;
+        self.x = filter.A * self.x + filter.B * self.u;
+        self.P = filter.A * self.P * filter.A.transpose() + filter.Q;
+
+        // Keep the mean within the feasible set
+        self.x = clamp(self.x, filter.forecast_bounds.as_ref());
+        Some((self.x, self.P))
     }
 }
@@ -0,0 +1,123 @@
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use alloc::vec::Vec;
+use nalgebra::{DMatrix, DVector, RealField, SMatrix};
+
+/// Maximum-a-posteriori estimate of a full state trajectory.
+pub struct BatchSolution<const Nx: usize, F: RealField + Copy> {
+    /// Estimated states `x[0]` through `x[N]`.
+    pub states: Vec<SMatrix<F, Nx, 1>>,
+
+    /// Joint covariance of all estimated states, in the same order as `states`.
+    pub covariance: DMatrix<F>,
+}
+
+/// Batch (full-information) estimator, solving for a whole state trajectory at once
+/// rather than recursively. Intended for short sequences, as the joint system is dense.
+pub struct BatchEstimator<const Nx: usize, const Nu: usize, F: RealField + Copy> {
+
+    // Model propagation matrix
+    A: SMatrix<F, Nx, Nx>,
+
+    // Input matrix
+    B: SMatrix<F, Nx, Nu>,
+
+    // Model noise covariance matrix
+    Q: SMatrix<F, Nx, Nx>,
+
+    // Prior on the initial state
+    x_init: SMatrix<F, Nx, 1>,
+    P_init: SMatrix<F, Nx, Nx>,
+
+}
+
+impl<const Nx: usize, const Nu: usize, F: RealField + Copy> BatchEstimator<Nx, Nu, F> {
+    /// Provide batch estimator with the model and initial state prior
+    pub fn new(
+        A: SMatrix<F, Nx, Nx>,
+        B: Option<SMatrix<F, Nx, Nu>>,
+        Q: SMatrix<F, Nx, Nx>,
+        x_init: SMatrix<F, Nx, 1>,
+        P_init: SMatrix<F, Nx, Nx>,
+    ) -> Self {
+        Self {
+            A,
+            B: B.unwrap_or_else(SMatrix::zeros),
+            Q,
+            x_init,
+            P_init,
+        }
+    }
+
+    /// Solve for the trajectory `x[0]` through `x[N]`, with `N = inputs.len()`.
+    ///
+    /// Step `k` follows the same order as the recursive filter: the optional measurement
+    /// `measurements[k]` of `x[k]` is applied, after which `x[k+1] = A*x[k] + B*u[k]`.
+    /// Returns `None` if the lengths differ or any of `P_init`, `Q`, `R` or the joint
+    /// information matrix are not invertible.
+    pub fn solve<const Ny: usize>(
+        &self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        inputs: &[SMatrix<F, Nu, 1>],
+        measurements: &[Option<SMatrix<F, Ny, 1>>],
+    ) -> Option<BatchSolution<Nx, F>> {
+        if inputs.len() != measurements.len() { return None }
+        let steps = inputs.len() + 1;
+
+        let P_inv = self.P_init.try_inverse()?;
+        let Q_inv = self.Q.try_inverse()?;
+        let R_inv = R.try_inverse()?;
+
+        // Normal equations of the stacked weighted least-squares problem
+        let mut H = DMatrix::<F>::zeros(steps * Nx, steps * Nx);
+        let mut b = DVector::<F>::zeros(steps * Nx);
+
+        // Initial state prior
+        add_block(&mut H, 0, 0, &P_inv);
+        add_rows(&mut b, 0, &(P_inv * self.x_init));
+
+        for (k, (u, y)) in inputs.iter().zip(measurements).enumerate() {
+            let (i, j) = (k * Nx, (k + 1) * Nx);
+
+            // Dynamics constraint `x[k+1] - A*x[k] - B*u[k]`
+            let AtQ = self.A.transpose() * Q_inv;
+            let d = self.B * u;
+            add_block(&mut H, i, i, &(AtQ * self.A));
+            add_block(&mut H, j, j, &Q_inv);
+            add_block(&mut H, i, j, &-AtQ);
+            add_block(&mut H, j, i, &-AtQ.transpose());
+            add_rows(&mut b, i, &-(AtQ * d));
+            add_rows(&mut b, j, &(Q_inv * d));
+
+            // Measurement constraint `y[k] - C*x[k]`
+            if let Some(y) = y {
+                let CtR = C.transpose() * R_inv;
+                add_block(&mut H, i, i, &(CtR * C));
+                add_rows(&mut b, i, &(CtR * y));
+            }
+        }
+
+        let chol = H.cholesky()?;
+        let z = chol.solve(&b);
+        let states = (0..steps).map(|k| z.fixed_rows::<Nx>(k * Nx).into_owned()).collect();
+
+        Some(BatchSolution {
+            states,
+            covariance: chol.inverse(),
+        })
+    }
+}
+
+// Add `M` to the `Nx x Nx` block of `H` starting at row `i` and column `j`
+fn add_block<const Nx: usize, F: RealField + Copy>(H: &mut DMatrix<F>, i: usize, j: usize, M: &SMatrix<F, Nx, Nx>) {
+    let mut block = H.fixed_view_mut::<Nx, Nx>(i, j);
+    block += M;
+}
+
+// Add `v` to the `Nx` rows of `b` starting at row `i`
+fn add_rows<const Nx: usize, F: RealField + Copy>(b: &mut DVector<F>, i: usize, v: &SMatrix<F, Nx, 1>) {
+    let mut rows = b.fixed_rows_mut::<Nx>(i);
+    rows += v;
+}
@@ -0,0 +1,97 @@
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use nalgebra::{RealField, SMatrix};
+
+use crate::kalman::{self, KalmanError};
+
+/// Linear `Nx`-dimensional Kalman filter with `Nb` augmented measurement bias states.
+///
+/// The bias states follow random-walk dynamics `b[k+1] = b[k] + w[k]` and are estimated
+/// jointly with the base states. `Na` is the augmented dimension and must equal `Nx + Nb`.
+pub struct KalmanFilter<const Nx: usize, const Nu: usize, const Nb: usize, const Na: usize, F: RealField + Copy> {
+
+    // Augmented filter over the state vector `[x, b]`
+    filter: kalman::KalmanFilter<Na, Nu, F>,
+
+}
+
+impl<const Nx: usize, const Nu: usize, const Nb: usize, const Na: usize, F: RealField + Copy> KalmanFilter<Nx, Nu, Nb, Na, F> {
+    /// Provide kalman filter with the base model, bias random-walk noise `Q_bias` and
+    /// initial bias covariance `P_bias`. The bias estimate is initialized to zero.
+    pub fn new(
+        A: SMatrix<F, Nx, Nx>,
+        B: Option<SMatrix<F, Nx, Nu>>,
+        Q: SMatrix<F, Nx, Nx>,
+        Q_bias: SMatrix<F, Nb, Nb>,
+        x_init: SMatrix<F, Nx, 1>,
+        P_init: SMatrix<F, Nx, Nx>,
+        P_bias: SMatrix<F, Nb, Nb>,
+    ) -> Self {
+        const { assert!(Na == Nx + Nb, "augmented dimension `Na` must equal `Nx + Nb`") };
+
+        // Base dynamics on the first block, random walk on the bias block
+        let mut A_a = SMatrix::<F, Na, Na>::identity();
+        A_a.fixed_view_mut::<Nx, Nx>(0, 0).copy_from(&A);
+
+        let mut B_a = SMatrix::<F, Na, Nu>::zeros();
+        if let Some(B) = B {
+            B_a.fixed_view_mut::<Nx, Nu>(0, 0).copy_from(&B);
+        }
+
+        let mut Q_a = SMatrix::<F, Na, Na>::zeros();
+        Q_a.fixed_view_mut::<Nx, Nx>(0, 0).copy_from(&Q);
+        Q_a.fixed_view_mut::<Nb, Nb>(Nx, Nx).copy_from(&Q_bias);
+
+        let mut x_a = SMatrix::<F, Na, 1>::zeros();
+        x_a.fixed_view_mut::<Nx, 1>(0, 0).copy_from(&x_init);
+
+        let mut P_a = SMatrix::<F, Na, Na>::zeros();
+        P_a.fixed_view_mut::<Nx, Nx>(0, 0).copy_from(&P_init);
+        P_a.fixed_view_mut::<Nb, Nb>(Nx, Nx).copy_from(&P_bias);
+
+        Self {
+            filter: kalman::KalmanFilter::new(A_a, Some(B_a), Q_a, x_a, P_a),
+        }
+    }
+
+    /// Predict new state. The bias estimate is carried over unchanged.
+    pub fn predict(&mut self) {
+        self.filter.predict()
+    }
+
+    /// Predict new state using input. The bias estimate is carried over unchanged.
+    pub fn predict_with_input(&mut self, u: SMatrix<F, Nu, 1>) {
+        self.filter.predict_with_input(u)
+    }
+
+    /// Update filter with a biased measurement `y = C*x + C_bias*b + v`.
+    /// Unbiased sensors can use a zero `C_bias`.
+    pub fn update<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>,      // Output matrix
+        C_bias: &SMatrix<F, Ny, Nb>, // Bias output matrix
+        R: &SMatrix<F, Ny, Ny>,      // Covariance
+        y: &SMatrix<F, Ny, 1>,       // Measurement
+    ) -> Result<(), KalmanError> {
+        let mut C_a = SMatrix::<F, Ny, Na>::zeros();
+        C_a.fixed_view_mut::<Ny, Nx>(0, 0).copy_from(C);
+        C_a.fixed_view_mut::<Ny, Nb>(0, Nx).copy_from(C_bias);
+        self.filter.update(&C_a, R, y)
+    }
+
+    /// Get base state vector `x`, without the bias states.
+    pub fn get_state(&self) -> SMatrix<F, Nx, 1> {
+        self.filter.get_state().fixed_rows::<Nx>(0).into_owned()
+    }
+
+    /// Get the estimated measurement bias `b`.
+    pub fn get_bias_estimate(&self) -> SMatrix<F, Nb, 1> {
+        self.filter.get_state().fixed_rows::<Nb>(Nx).into_owned()
+    }
+
+    /// Get the underlying augmented filter over `[x, b]`.
+    pub fn augmented(&self) -> &kalman::KalmanFilter<Na, Nu, F> {
+        &self.filter
+    }
+}
@@ -0,0 +1,90 @@
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use nalgebra::{RealField, SMatrix};
+
+use crate::kalman::KalmanError;
+
+/// Linear state-space `Nx`-dimensional Kalman filter operating on borrowed model matrices.
+///
+/// The model matrices `A`, `B` and `Q` are borrowed rather than owned, such that they can be
+/// kept in a preallocated pool and shared across many filters. Only the estimate is owned.
+pub struct KalmanFilter<'a, const Nx: usize, const Nu: usize, F: RealField + Copy> {
+
+    // Model propagation matrix
+    A: &'a SMatrix<F, Nx, Nx>,
+
+    // Input matrix
+    B: Option<&'a SMatrix<F, Nx, Nu>>,
+
+    // Model noise covariance matrix
+    Q: &'a SMatrix<F, Nx, Nx>,
+
+    // State vector and covariance matrix of the current estimate
+    x: SMatrix<F, Nx, 1>,
+    P: SMatrix<F, Nx, Nx>,
+
+}
+
+impl<'a, const Nx: usize, const Nu: usize, F: RealField + Copy> KalmanFilter<'a, Nx, Nu, F> {
+    /// Provide kalman filter with borrowed model matrices and initial values
+    pub fn new(
+        A: &'a SMatrix<F, Nx, Nx>,
+        B: Option<&'a SMatrix<F, Nx, Nu>>,
+        Q: &'a SMatrix<F, Nx, Nx>,
+        x_init: SMatrix<F, Nx, 1>,
+        P_init: SMatrix<F, Nx, Nx>,
+    ) -> Self {
+        Self { A, B, Q, x: x_init, P: P_init }
+    }
+
+    /// Predict new state. If plant dynamics are time-dependent,
+    /// this method (or `.predict_with_input`) must be called at the correct frequency.
+    pub fn predict(&mut self) {
+        self.x = self.A * self.x;
+        self.P = self.A * self.P * self.A.transpose() + self.Q;
+    }
+
+    /// Predict new state using input. If plant dynamics are time-dependent,
+    /// this method (or `.predict`) must be called at the correct frequency.
+    pub fn predict_with_input(&mut self, u: SMatrix<F, Nu, 1>) {
+        self.predict();
+        if let Some(B) = self.B {
+            self.x += B * u;
+        }
+    }
+
+    /// Update filter with new measurements.
+    ///
+    /// Returns `KalmanError::SingularInnovation` and leaves the estimate unchanged if the
+    /// innovation covariance `S` cannot be inverted.
+    pub fn update<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> Result<(), KalmanError> {
+        // Innovation (or pre-fit residual) covariance
+        let S = C * self.P * C.transpose() + R;
+
+        // Optimal Kalman gain
+        let Sinv = S.try_inverse().ok_or(KalmanError::SingularInnovation)?;
+        let K = self.P * C.transpose() * Sinv;
+
+        // Updated (a posteriori) estimate and symmetrized covariance
+        self.x += K * (y - C * self.x);
+        let P = (SMatrix::identity() - K * C) * self.P;
+        self.P = (P + P.transpose()).scale(nalgebra::convert(0.5));
+        Ok(())
+    }
+
+    /// Get state vector `x`.
+    pub fn get_state(&self) -> SMatrix<F, Nx, 1> {
+        self.x
+    }
+
+    /// Get the covariance matrix `P` of the current estimate.
+    pub fn get_covariance(&self) -> SMatrix<F, Nx, Nx> {
+        self.P
+    }
+}
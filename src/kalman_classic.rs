@@ -3,6 +3,8 @@
 
 use nalgebra::{ComplexField, SMatrix, Scalar, SimdValue};
 
+use crate::kalman::KalmanError;
+
 struct VecMat<const N: usize, F: Scalar + SimdValue + ComplexField + Copy> {
     x: SMatrix<F, N, 1>,
     P: SMatrix<F, N, N>,
@@ -73,9 +75,6 @@ impl<const Nx: usize, const Nu: usize, const Ny: usize, F: Scalar + SimdValue +
 
             // Prediction based on new observations
             Some(post) => {
-                // Finish calc for P_post and symmetrize
-                post.P = post.P * self.prio.P;
-
                 // Symmetrize
                 post.P = (post.P + post.P.transpose()).scale(nalgebra::convert(0.5));
 
@@ -89,29 +88,31 @@ impl<const Nx: usize, const Nu: usize, const Ny: usize, F: Scalar + SimdValue +
         }
     }
 
-    /// Update filter with new measurements
-    pub fn update(&mut self, y: &SMatrix<F, Ny, 1>) {
+    /// Update filter with new measurements.
+    /// Multiple updates between predictions are applied sequentially, each against the
+    /// posterior of the previous one.
+    ///
+    /// Returns `KalmanError::SingularInnovation` and leaves the estimate unchanged if the
+    /// innovation covariance `S` cannot be inverted.
+    pub fn update(&mut self, y: &SMatrix<F, Ny, 1>) -> Result<(), KalmanError> {
+        let current = self.post.as_ref().unwrap_or(&self.prio);
+
         // Measurement prediction residual
-        let y_res = y - self.C * self.prio.x;
+        let y_res = y - self.C * current.x;
 
         // Innovation (or pre-fit residual) covariance
-        let S = self.C * self.prio.P * self.C.transpose() + self.R;
+        let S = self.C * current.P * self.C.transpose() + self.R;
 
         // Optimal Kalman gain
-        let Some(Sinv) = S.try_inverse() else { return };
-        let K = self.prio.P * self.C.transpose() * Sinv;
+        let Sinv = S.try_inverse().ok_or(KalmanError::SingularInnovation)?;
+        let K = current.P * self.C.transpose() * Sinv;
 
         // Updated (a posteriori) estimate covariance
-        self.post = Some(match self.post.as_mut() {
-            Some(post) => VecMat {
-                x: post.x + K * y_res,
-                P: post.P - K * self.C,
-            },
-            None => VecMat {
-                x: self.prio.x + K * y_res,
-                P: SMatrix::identity() - K * self.C,
-            },
+        self.post = Some(VecMat {
+            x: current.x + K * y_res,
+            P: (SMatrix::identity() - K * self.C) * current.P,
         });
+        Ok(())
     }
 
     /// Get state vector `x`.
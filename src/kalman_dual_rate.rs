@@ -0,0 +1,115 @@
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use nalgebra::{RealField, SMatrix};
+
+use crate::kalman::{KalmanError, KalmanFilter};
+
+/// Dual-rate wrapper of a linear Kalman filter, predicting at a fast rate (such as an IMU)
+/// and updating at a slow rate (such as GPS), with `ratio` predictions per update.
+pub struct DualRateFilter<const Nx: usize, const Nu: usize, F: RealField + Copy> {
+
+    // Wrapped filter
+    filter: KalmanFilter<Nx, Nu, F>,
+
+    // Number of predictions per update
+    ratio: usize,
+
+    // Predictions since the last update
+    count: usize,
+
+}
+
+impl<const Nx: usize, const Nu: usize, F: RealField + Copy> DualRateFilter<Nx, Nu, F> {
+    /// Wrap a filter with `ratio` predictions per update, clamped to at least one.
+    /// The first update is due immediately.
+    pub fn new(filter: KalmanFilter<Nx, Nu, F>, ratio: usize) -> Self {
+        let ratio = ratio.max(1);
+        Self { filter, ratio, count: ratio }
+    }
+
+    /// Predict new state at the fast rate.
+    /// Returns `true` if a slow-rate update is due.
+    pub fn predict(&mut self) -> bool {
+        self.filter.predict();
+        self.count += 1;
+        self.is_update_due()
+    }
+
+    /// Predict new state using input at the fast rate.
+    /// Returns `true` if a slow-rate update is due.
+    pub fn predict_with_input(&mut self, u: SMatrix<F, Nu, 1>) -> bool {
+        self.filter.predict_with_input(u);
+        self.count += 1;
+        self.is_update_due()
+    }
+
+    /// Check whether `ratio` predictions have been made since the last update.
+    pub fn is_update_due(&self) -> bool {
+        self.count >= self.ratio
+    }
+
+    /// Update filter with new slow-rate measurements, restarting the count of predictions.
+    pub fn update<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> Result<(), KalmanError> {
+        self.filter.update(C, R, y)?;
+        self.count = 0;
+        Ok(())
+    }
+
+    /// Update filter with new slow-rate measurements if an update is due, where `measure` is
+    /// only called to take the measurement when it is due.
+    /// Returns `Ok(true)` if the update was made.
+    pub fn update_if_due<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        measure: impl FnOnce() -> SMatrix<F, Ny, 1>,
+    ) -> Result<bool, KalmanError> {
+        if !self.is_update_due() {
+            return Ok(false);
+        }
+        self.update(C, R, &measure())?;
+        Ok(true)
+    }
+
+    /// Advance one fast-rate step, predicting with input `u` and then updating with the
+    /// slow-rate measurement `y` if an update is due. When no measurement is available the
+    /// update stays due until one is given, so `y` can be passed whenever the slow sensor has data.
+    /// Returns `Ok(true)` if the update was made.
+    pub fn step<const Ny: usize>(
+        &mut self,
+        u: SMatrix<F, Nu, 1>,           // Input
+        C: &SMatrix<F, Ny, Nx>,         // Output matrix
+        R: &SMatrix<F, Ny, Ny>,         // Covariance
+        y: Option<&SMatrix<F, Ny, 1>>,  // Measurement, if available
+    ) -> Result<bool, KalmanError> {
+        let due = self.predict_with_input(u);
+        match y {
+            Some(y) if due => {
+                self.update(C, R, y)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Get state vector `x`.
+    pub fn get_state(&self) -> SMatrix<F, Nx, 1> {
+        self.filter.get_state()
+    }
+
+    /// Get the underlying filter.
+    pub fn filter(&self) -> &KalmanFilter<Nx, Nu, F> {
+        &self.filter
+    }
+
+    /// Get the underlying filter mutably.
+    pub fn filter_mut(&mut self) -> &mut KalmanFilter<Nx, Nu, F> {
+        &mut self.filter
+    }
+}
@@ -0,0 +1,118 @@
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use nalgebra::{DMatrix, DVector, RealField};
+
+use crate::kalman::KalmanError;
+
+/// Linear state-space Kalman filter with dimensions known only at runtime, such as for a
+/// state that grows with the number of tracked landmarks. The model is validated on
+/// construction and every measurement is checked against the state dimension.
+///
+/// Changing the state dimension is done by constructing a new filter from the current
+/// estimate, extended with the new states.
+pub struct DynKalmanFilter<F: RealField + Copy> {
+
+    // Model propagation matrix
+    A: DMatrix<F>,
+
+    // Input matrix
+    B: DMatrix<F>,
+
+    // Model noise covariance matrix
+    Q: DMatrix<F>,
+
+    // State vector and covariance matrix of the current estimate
+    x: DVector<F>,
+    P: DMatrix<F>,
+
+}
+
+impl<F: RealField + Copy> DynKalmanFilter<F> {
+    /// Provide kalman filter with all initial values. A missing `B` is taken as a system without
+    /// inputs. Returns `KalmanError::DimensionMismatch` unless `A`, `Q` and `P_init` are square
+    /// and `B` and `x_init` have as many rows as the state dimension.
+    pub fn new(
+        A: DMatrix<F>,
+        B: Option<DMatrix<F>>,
+        Q: DMatrix<F>,
+        x_init: DVector<F>,
+        P_init: DMatrix<F>,
+    ) -> Result<Self, KalmanError> {
+        let n = x_init.len();
+        let B = B.unwrap_or_else(|| DMatrix::zeros(n, 0));
+        if A.shape() != (n, n) || B.nrows() != n || Q.shape() != (n, n) || P_init.shape() != (n, n) {
+            return Err(KalmanError::DimensionMismatch);
+        }
+        Ok(Self { A, B, Q, x: x_init, P: P_init })
+    }
+
+    /// Predict new state. If plant dynamics are time-dependent,
+    /// this method (or `.predict_with_input`) must be called at the correct frequency.
+    pub fn predict(&mut self) {
+        let u = DVector::zeros(self.B.ncols());
+        self.propagate(&u)
+    }
+
+    /// Predict new state using input. If plant dynamics are time-dependent,
+    /// this method (or `.predict`) must be called at the correct frequency.
+    ///
+    /// Returns `KalmanError::DimensionMismatch` and leaves the estimate unchanged if `u` does not
+    /// match the columns of `B`.
+    pub fn predict_with_input(&mut self, u: &DVector<F>) -> Result<(), KalmanError> {
+        if u.len() != self.B.ncols() {
+            return Err(KalmanError::DimensionMismatch);
+        }
+        self.propagate(u);
+        Ok(())
+    }
+
+    /// Update filter with new measurements. Multiple updates between predictions are applied
+    /// sequentially, each against the posterior of the previous one.
+    ///
+    /// Returns `KalmanError::DimensionMismatch` unless `C` has as many columns as the state
+    /// dimension and `R` and `y` match its rows, or `KalmanError::SingularInnovation` if the
+    /// innovation covariance `S` cannot be inverted. The estimate is then left unchanged.
+    pub fn update(
+        &mut self,
+        C: &DMatrix<F>, // Output matrix
+        R: &DMatrix<F>, // Covariance
+        y: &DVector<F>, // Measurement
+    ) -> Result<(), KalmanError> {
+        let m = y.len();
+        if C.shape() != (m, self.dimension()) || R.shape() != (m, m) {
+            return Err(KalmanError::DimensionMismatch);
+        }
+
+        let y_res = y - C * &self.x;
+        let S = C * &self.P * C.transpose() + R;
+        let Sinv = S.try_inverse().ok_or(KalmanError::SingularInnovation)?;
+        let K = &self.P * C.transpose() * Sinv;
+
+        self.x += &K * y_res;
+        self.P = (DMatrix::identity(self.dimension(), self.dimension()) - K * C) * &self.P;
+        Ok(())
+    }
+
+    /// Get the state dimension.
+    pub fn dimension(&self) -> usize {
+        self.x.len()
+    }
+
+    /// Get state vector `x` of the current estimate.
+    pub fn get_state(&self) -> &DVector<F> {
+        &self.x
+    }
+
+    /// Get covariance matrix `P` of the current estimate.
+    pub fn get_covariance(&self) -> &DMatrix<F> {
+        &self.P
+    }
+
+    /// Propagate the estimate through the model with an input of validated dimension.
+    fn propagate(&mut self, u: &DVector<F>) {
+        self.x = &self.A * &self.x + &self.B * u;
+        let P = &self.A * &self.P * self.A.transpose() + &self.Q;
+        self.P = (&P + P.transpose()).scale(nalgebra::convert(0.5));
+    }
+}
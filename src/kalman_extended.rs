@@ -3,11 +3,25 @@
 
 use nalgebra::{ComplexField, SMatrix, Scalar, SimdValue};
 
+use crate::kalman::KalmanError;
+
 struct VecMat<const N: usize, F: Scalar + SimdValue + ComplexField + Copy> {
     x: SMatrix<F, N, 1>,
     P: SMatrix<F, N, N>,
 }
 
+/// Continuous-time model used by `.predict_continuous`.
+struct ContinuousModel<const Nx: usize, const Nu: usize, F: Scalar + SimdValue + ComplexField + Copy> {
+    /// State derivative function on the form `x_dot = f(x,u)`
+    Ff: fn(SMatrix<F, Nx, 1>, SMatrix<F, Nu, 1>) -> SMatrix<F, Nx, 1>,
+
+    /// Jacobian of the state derivative function with respect to the state.
+    Fj: fn(SMatrix<F, Nx, 1>, SMatrix<F, Nu, 1>) -> SMatrix<F, Nx, Nx>,
+
+    /// Continuous-time model noise spectral density.
+    Q: SMatrix<F, Nx, Nx>,
+}
+
 /// Extended `Nx`-dimensional kalman filter implementation utilizing the `nalgebra` library.
 pub struct KalmanFilter<const Nx: usize, const Nu: usize, const Ny: usize, F: Scalar + SimdValue + ComplexField + Copy> {
 
@@ -37,10 +51,17 @@ pub struct KalmanFilter<const Nx: usize, const Nu: usize, const Ny: usize, F: Sc
     /// A posteriori state vector and covariance matrix.
     post: Option<VecMat<Nx, F>>,
 
+    /// Continuous-time model for continuous-discrete prediction.
+    continuous: Option<ContinuousModel<Nx, Nu, F>>,
+
+    /// Number of RK4 substeps used by continuous-discrete prediction.
+    substeps: usize,
+
 }
 
 impl<const Nx: usize, const Nu: usize, const Ny: usize, F: Scalar + SimdValue + ComplexField + Copy> KalmanFilter<Nx, Nu, Ny, F> {
     /// Provide kalman filter with all initial values
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         Ff: fn(SMatrix<F, Nx, 1>, SMatrix<F, Nu, 1>) -> SMatrix<F, Nx, 1>,
         Fj: fn(SMatrix<F, Nx, 1>, SMatrix<F, Nu, 1>) -> SMatrix<F, Nx, Nx>,
@@ -58,9 +79,28 @@ impl<const Nx: usize, const Nu: usize, const Ny: usize, F: Scalar + SimdValue +
                 P: P_init,
             },
             post: None,
+            continuous: None,
+            substeps: 10,
         }
     }
 
+    /// Set the continuous-time model `x_dot = f(x,u)`, its state jacobian and the
+    /// continuous model noise spectral density, used by `.predict_continuous`.
+    pub fn set_continuous_model(
+        &mut self,
+        Ff: fn(SMatrix<F, Nx, 1>, SMatrix<F, Nu, 1>) -> SMatrix<F, Nx, 1>,
+        Fj: fn(SMatrix<F, Nx, 1>, SMatrix<F, Nu, 1>) -> SMatrix<F, Nx, Nx>,
+        Q: SMatrix<F, Nx, Nx>,
+    ) {
+        self.continuous = Some(ContinuousModel { Ff, Fj, Q });
+    }
+
+    /// Set the number of RK4 substeps each continuous-discrete prediction is split into.
+    /// Defaults to 10.
+    pub fn set_substeps(&mut self, substeps: usize) {
+        self.substeps = substeps.max(1);
+    }
+
     /// Predict new state. If plant dynamics are time-dependent,
     /// this method (or `.predict_with_input`) must be called at the correct frequency.
     pub fn predict(&mut self) {
@@ -80,9 +120,6 @@ impl<const Nx: usize, const Nu: usize, const Ny: usize, F: Scalar + SimdValue +
 
             // Prediction based on new observations
             Some(post) => {
-                // Finish calc for P_post and symmetrize
-                post.P = post.P * self.prio.P;
-
                 // Symmetrize
                 post.P = (post.P + post.P.transpose()).scale(nalgebra::convert(0.5));
 
@@ -97,35 +134,81 @@ impl<const Nx: usize, const Nu: usize, const Ny: usize, F: Scalar + SimdValue +
         }
     }
 
-    /// Update filter with new measurements.
-    pub fn update(&mut self, y: &SMatrix<F, Ny, 1>) {
+    /// Predict new state `dt` forward in time using the continuous-time model.
+    /// Returns `KalmanError::NoContinuousModel` if no continuous model has been set with
+    /// `.set_continuous_model`, leaving the filter unchanged.
+    pub fn predict_continuous(&mut self, dt: F) -> Result<(), KalmanError> {
+        self.predict_continuous_with_input(dt, SMatrix::zeros())
+    }
+
+    /// Predict new state `dt` forward in time using the continuous-time model and control input.
+    /// The mean and covariance are jointly integrated with RK4, where the covariance follows
+    /// the matrix Riccati equation `P_dot = F*P + P*F^T + Q`, with `F` the state jacobian.
+    /// Returns `KalmanError::NoContinuousModel` if no continuous model has been set with
+    /// `.set_continuous_model`, leaving the filter unchanged.
+    pub fn predict_continuous_with_input(&mut self, dt: F, u: SMatrix<F, Nu, 1>) -> Result<(), KalmanError> {
+        let model = self.continuous.as_ref().ok_or(KalmanError::NoContinuousModel)?;
+
+        let current = self.post.as_ref().unwrap_or(&self.prio);
+        let (mut x, mut P) = (current.x, current.P);
+
+        // Joint derivative of mean and covariance
+        let derivative = |x: SMatrix<F, Nx, 1>, P: SMatrix<F, Nx, Nx>| {
+            let Fj = (model.Fj)(x, u);
+            ((model.Ff)(x, u), Fj * P + P * Fj.transpose() + model.Q)
+        };
+
+        let h = dt / nalgebra::convert(self.substeps as f64);
+        let half: F = nalgebra::convert(0.5);
+        let sixth: F = nalgebra::convert(1.0 / 6.0);
+        let two: F = nalgebra::convert(2.0);
+        for _ in 0..self.substeps {
+            let (k1x, k1P) = derivative(x, P);
+            let (k2x, k2P) = derivative(x + k1x * (h * half), P + k1P * (h * half));
+            let (k3x, k3P) = derivative(x + k2x * (h * half), P + k2P * (h * half));
+            let (k4x, k4P) = derivative(x + k3x * h, P + k3P * h);
+            x += (k1x + k2x * two + k3x * two + k4x) * (h * sixth);
+            P += (k1P + k2P * two + k3P * two + k4P) * (h * sixth);
+        }
+
+        // Update priors and set posteriors to none
+        self.prio.x = x;
+        self.prio.P = (P + P.transpose()) * half;
+        self.post = None;
+        Ok(())
+    }
+
+    /// Update filter with new measurements, see `.update_with_input`.
+    pub fn update(&mut self, y: &SMatrix<F, Ny, 1>) -> Result<(), KalmanError> {
         self.update_with_input(y,SMatrix::zeros())
     }
 
     /// Update filter with new measurements and control input.
-    pub fn update_with_input(&mut self, y: &SMatrix<F, Ny, 1>, u: SMatrix<F, Nu, 1>) {
+    /// Multiple updates between predictions are applied sequentially, each linearized about
+    /// the posterior of the previous one.
+    ///
+    /// Returns `KalmanError::SingularInnovation` and leaves the estimate unchanged if the
+    /// innovation covariance `S` cannot be inverted.
+    pub fn update_with_input(&mut self, y: &SMatrix<F, Ny, 1>, u: SMatrix<F, Nu, 1>) -> Result<(), KalmanError> {
+        let current = self.post.as_ref().unwrap_or(&self.prio);
+
         // Measurement prediction residual
-        let y_res = y - (self.Hf)(self.prio.x,u);
+        let y_res = y - (self.Hf)(current.x,u);
 
         // Innovation (or pre-fit residual) covariance
-        let Hj = (self.Hj)(self.prio.x,u);
-        let S = Hj * self.prio.P * Hj.transpose() + self.R;
+        let Hj = (self.Hj)(current.x,u);
+        let S = Hj * current.P * Hj.transpose() + self.R;
 
         // Optimal Kalman gain
-        let Some(Sinv) = S.try_inverse() else { return };
-        let K = self.prio.P * Hj.transpose() * Sinv;
+        let Sinv = S.try_inverse().ok_or(KalmanError::SingularInnovation)?;
+        let K = current.P * Hj.transpose() * Sinv;
 
         // Updated (a posteriori) estimate covariance
-        self.post = Some(match self.post.as_mut() {
-            Some(post) => VecMat {
-                x: post.x + K * y_res,
-                P: post.P - K * Hj,
-            },
-            None => VecMat {
-                x: self.prio.x + K * y_res,
-                P: SMatrix::identity() - K * Hj,
-            },
+        self.post = Some(VecMat {
+            x: current.x + K * y_res,
+            P: (SMatrix::identity() - K * Hj) * current.P,
         });
+        Ok(())
     }
 
     /// Get state vector `x`.
@@ -133,4 +216,10 @@ impl<const Nx: usize, const Nu: usize, const Ny: usize, F: Scalar + SimdValue +
     pub fn get_state(&self) -> SMatrix<F, Nx, 1> {
         self.post.as_ref().unwrap_or(&self.prio).x
     }
+
+    /// Get covariance matrix `P`.
+    /// Returns the posterior covariance if it exists, otherwise returns priori covariance.
+    pub fn get_covariance(&self) -> SMatrix<F, Nx, Nx> {
+        self.post.as_ref().unwrap_or(&self.prio).P
+    }
 }
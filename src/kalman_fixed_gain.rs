@@ -0,0 +1,72 @@
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use nalgebra::{RealField, SMatrix};
+
+/// Minimal fixed-gain estimator, applying a constant Kalman gain `K` without propagating a
+/// covariance. Typically exported from a converged filter with
+/// `KalmanFilter::export_fixed_gain_estimator`, for deployment on very constrained targets.
+#[derive(Clone)]
+pub struct FixedGainEstimator<const Nx: usize, const Nu: usize, const Ny: usize, F: RealField + Copy> {
+
+    // Model propagation matrix
+    A: SMatrix<F, Nx, Nx>,
+
+    // Input matrix
+    B: SMatrix<F, Nx, Nu>,
+
+    // Output matrix
+    C: SMatrix<F, Ny, Nx>,
+
+    // Constant Kalman gain
+    K: SMatrix<F, Nx, Ny>,
+
+    // State vector
+    x: SMatrix<F, Nx, 1>,
+
+}
+
+impl<const Nx: usize, const Nu: usize, const Ny: usize, F: RealField + Copy> FixedGainEstimator<Nx, Nu, Ny, F> {
+    /// Provide estimator with model, constant gain and initial state
+    pub fn new(
+        A: SMatrix<F, Nx, Nx>,
+        B: SMatrix<F, Nx, Nu>,
+        C: SMatrix<F, Ny, Nx>,
+        K: SMatrix<F, Nx, Ny>,
+        x_init: SMatrix<F, Nx, 1>,
+    ) -> Self {
+        Self { A, B, C, K, x: x_init }
+    }
+
+    /// Predict new state.
+    pub fn predict(&mut self) {
+        self.x = self.A * self.x;
+    }
+
+    /// Predict new state using input.
+    pub fn predict_with_input(&mut self, u: SMatrix<F, Nu, 1>) {
+        self.x = self.A * self.x + self.B * u;
+    }
+
+    /// Update estimator with new measurements, using the constant gain.
+    pub fn update(&mut self, y: &SMatrix<F, Ny, 1>) {
+        self.x += self.K * (y - self.C * self.x);
+    }
+
+    /// Predict with input followed by an update, returning the new state.
+    pub fn step(&mut self, u: SMatrix<F, Nu, 1>, y: &SMatrix<F, Ny, 1>) -> SMatrix<F, Nx, 1> {
+        self.predict_with_input(u);
+        self.update(y);
+        self.x
+    }
+
+    /// Get state vector `x`.
+    pub fn get_state(&self) -> SMatrix<F, Nx, 1> {
+        self.x
+    }
+
+    /// Get the constant Kalman gain `K`.
+    pub fn get_gain(&self) -> SMatrix<F, Nx, Ny> {
+        self.K
+    }
+}
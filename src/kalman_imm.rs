@@ -0,0 +1,134 @@
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use nalgebra::{RealField, SMatrix};
+
+use crate::kalman::{KalmanError, KalmanFilter};
+
+/// Interacting Multiple Model (IMM) estimator over `M` linear Kalman filters, such as for targets
+/// switching between cruising and maneuvering. Each filter holds one model with its own `A`, `B`
+/// and `Q`, and the active model switches as a Markov chain.
+///
+/// Each prediction mixes the model estimates according to the switching probabilities before
+/// predicting each filter, and each update reweights the models by their measurement likelihood.
+pub struct ImmEstimator<const Nx: usize, const Nu: usize, const M: usize, F: RealField + Copy> {
+
+    // Filter of each model
+    filters: [KalmanFilter<Nx, Nu, F>; M],
+
+    // Markov switching probabilities, with element `(i, j)` from model `i` to model `j`
+    transition: SMatrix<F, M, M>,
+
+    // Probability of each model being active
+    weights: SMatrix<F, M, 1>,
+
+}
+
+impl<const Nx: usize, const Nu: usize, const M: usize, F: RealField + Copy> ImmEstimator<Nx, Nu, M, F> {
+    /// Provide estimator with the filter of each model, the switching probabilities `transition`
+    /// with rows summing to one, and the initial model probabilities `weights` summing to one.
+    pub fn new(
+        filters: [KalmanFilter<Nx, Nu, F>; M],
+        transition: SMatrix<F, M, M>,
+        weights: SMatrix<F, M, 1>,
+    ) -> Self {
+        Self { filters, transition, weights }
+    }
+
+    /// Predict new state, see `.predict_with_input`.
+    pub fn predict(&mut self) {
+        self.predict_with_input(SMatrix::zeros())
+    }
+
+    /// Predict new state using input. Each filter is restarted from its mix of the model
+    /// estimates, conditioned on switching into its model, before predicting it. The model
+    /// probabilities become the predicted probabilities after the switch.
+    pub fn predict_with_input(&mut self, u: SMatrix<F, Nu, 1>) {
+        // Predicted model probabilities `c_j = sum_i(T_ij * w_i)`
+        let c = self.transition.transpose() * self.weights;
+
+        let estimates: [_; M] = core::array::from_fn(|i| (self.filters[i].get_state(), self.filters[i].get_covariance()));
+        for (j, filter) in self.filters.iter_mut().enumerate() {
+            if c[j] <= F::zero() {
+                filter.predict_with_input(u);
+                continue;
+            }
+
+            // Mixing probabilities `w_i|j = T_ij * w_i / c_j` of each model into model `j`
+            let mixing: SMatrix<F, M, 1> = SMatrix::from_fn(|i, _| self.transition[(i, j)] * self.weights[i] / c[j]);
+            let (x, P) = combine(&estimates, &mixing);
+            filter.reset(x, P);
+            filter.predict_with_input(u);
+        }
+        self.weights = c;
+    }
+
+    /// Update each filter with new measurements, and reweight the models by the likelihood
+    /// of the measurement under each of them.
+    ///
+    /// Returns an error and leaves the estimate unchanged if the innovation covariance of any
+    /// model is not positive-definite, or if the update of any model fails.
+    pub fn update<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> Result<(), KalmanError> {
+        let mut log_likelihoods = SMatrix::<F, M, 1>::zeros();
+        for (j, filter) in self.filters.iter().enumerate() {
+            log_likelihoods[j] = filter
+                .measurement_log_likelihood(C, R, y)
+                .ok_or(KalmanError::SingularInnovation)?;
+        }
+
+        // Update copies, such that a failing model leaves all of them unchanged
+        let mut filters = self.filters.clone();
+        for filter in filters.iter_mut() {
+            filter.update(C, R, y)?;
+        }
+        self.filters = filters;
+
+        // Bayes rule, relative to the most likely model to avoid underflow
+        let max = log_likelihoods.max();
+        let weights = self.weights.zip_map(&log_likelihoods, |w, l| w * (l - max).exp());
+        let total = weights.sum();
+        if total > F::zero() {
+            self.weights = weights / total;
+        }
+        Ok(())
+    }
+
+    /// Get the combined state vector `x`, weighted by the model probabilities.
+    pub fn get_state(&self) -> SMatrix<F, Nx, 1> {
+        self.filters.iter().zip(self.weights.iter()).fold(SMatrix::zeros(), |acc, (filter, &w)| acc + filter.get_state() * w)
+    }
+
+    /// Get the combined covariance matrix `P`, including the spread between the model estimates.
+    pub fn get_covariance(&self) -> SMatrix<F, Nx, Nx> {
+        let estimates: [_; M] = core::array::from_fn(|i| (self.filters[i].get_state(), self.filters[i].get_covariance()));
+        combine(&estimates, &self.weights).1
+    }
+
+    /// Get the probability of each model being active.
+    pub fn get_weights(&self) -> SMatrix<F, M, 1> {
+        self.weights
+    }
+
+    /// Get the filters of each model.
+    pub fn filters(&self) -> &[KalmanFilter<Nx, Nu, F>; M] {
+        &self.filters
+    }
+}
+
+/// Moment-matched mean and covariance of a Gaussian mixture of `estimates` with `weights`.
+fn combine<const Nx: usize, const M: usize, F: RealField + Copy>(
+    estimates: &[(SMatrix<F, Nx, 1>, SMatrix<F, Nx, Nx>); M],
+    weights: &SMatrix<F, M, 1>,
+) -> (SMatrix<F, Nx, 1>, SMatrix<F, Nx, Nx>) {
+    let x = estimates.iter().zip(weights.iter()).fold(SMatrix::zeros(), |acc, ((x, _), &w)| acc + x * w);
+    let P = estimates.iter().zip(weights.iter()).fold(SMatrix::zeros(), |acc, ((x_i, P_i), &w)| {
+        let dx = x_i - x;
+        acc + (P_i + dx * dx.transpose()) * w
+    });
+    (x, P)
+}
@@ -0,0 +1,131 @@
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use nalgebra::{RealField, SMatrix};
+
+use crate::kalman::KalmanError;
+
+/// Linear state-space `Nx`-dimensional Kalman filter in information form, tracking the
+/// information vector `y = P^-1 * x` and the information matrix `Y = P^-1`.
+///
+/// Measurement updates are additive in information form, such that fusing many independent
+/// sensors needs no innovation covariance inverses, and the state is only solved for when read.
+/// An uninformative prior is represented exactly by a zero information matrix.
+pub struct KalmanFilter<const Nx: usize, const Nu: usize, F: RealField + Copy> {
+
+    // Model propagation matrix
+    A: SMatrix<F, Nx, Nx>,
+
+    // Input matrix
+    B: SMatrix<F, Nx, Nu>,
+
+    // Model noise covariance matrix
+    Q: SMatrix<F, Nx, Nx>,
+
+    // Information vector and information matrix of the current estimate
+    y: SMatrix<F, Nx, 1>,
+    Y: SMatrix<F, Nx, Nx>,
+
+}
+
+impl<const Nx: usize, const Nu: usize, F: RealField + Copy> KalmanFilter<Nx, Nu, F> {
+    /// Provide kalman filter with the model and the initial information vector `y_init = P^-1 * x`
+    /// and information matrix `Y_init = P^-1`.
+    pub fn new(
+        A: SMatrix<F, Nx, Nx>,
+        B: Option<SMatrix<F, Nx, Nu>>,
+        Q: SMatrix<F, Nx, Nx>,
+        y_init: SMatrix<F, Nx, 1>,
+        Y_init: SMatrix<F, Nx, Nx>,
+    ) -> Self {
+        Self {
+            A,
+            B: B.unwrap_or_else(SMatrix::zeros),
+            Q,
+            y: y_init,
+            Y: Y_init,
+        }
+    }
+
+    /// Predict new state. If plant dynamics are time-dependent,
+    /// this method (or `.predict_with_input`) must be called at the correct frequency.
+    ///
+    /// Returns `KalmanError::NonPositiveDefiniteCovariance` and leaves the estimate unchanged
+    /// if the prediction cannot be made, see `.predict_with_input`.
+    pub fn predict(&mut self) -> Result<(), KalmanError> {
+        self.predict_with_input(SMatrix::zeros())
+    }
+
+    /// Predict new state using input. If plant dynamics are time-dependent,
+    /// this method (or `.predict`) must be called at the correct frequency.
+    ///
+    /// With an invertible `A` the prediction is made in information form, with
+    /// `M = A^-T * Y * A^-1` giving `Y = (I + M*Q)^-1 * M`, which needs only one `Nx x Nx`
+    /// inverse and keeps a zero information matrix zero. With a singular `A` it is made in
+    /// covariance form, requiring an inverse both ways. Returns
+    /// `KalmanError::NonPositiveDefiniteCovariance` and leaves the estimate unchanged if the
+    /// predicted information matrix cannot be computed, or in covariance form if the
+    /// information matrix or the predicted covariance is not positive-definite.
+    pub fn predict_with_input(&mut self, u: SMatrix<F, Nu, 1>) -> Result<(), KalmanError> {
+        let Some(Ainv) = self.A.try_inverse() else {
+            return self.predict_covariance_form(u);
+        };
+
+        let M = Ainv.transpose() * self.Y * Ainv;
+        let Linv = (SMatrix::<F, Nx, Nx>::identity() + M * self.Q)
+            .try_inverse()
+            .ok_or(KalmanError::NonPositiveDefiniteCovariance)?;
+        let Y = Linv * M;
+        let Y = (Y + Y.transpose()).scale(nalgebra::convert(0.5));
+
+        self.y = Linv * Ainv.transpose() * self.y + Y * self.B * u;
+        self.Y = Y;
+        Ok(())
+    }
+
+    /// Predict new state using input in covariance form, for a singular `A`.
+    fn predict_covariance_form(&mut self, u: SMatrix<F, Nu, 1>) -> Result<(), KalmanError> {
+        let chol = self.Y.cholesky().ok_or(KalmanError::NonPositiveDefiniteCovariance)?;
+        let x = self.A * chol.solve(&self.y) + self.B * u;
+        let P = self.A * chol.inverse() * self.A.transpose() + self.Q;
+
+        let Y = P.cholesky().ok_or(KalmanError::NonPositiveDefiniteCovariance)?.inverse();
+        self.y = Y * x;
+        self.Y = Y;
+        Ok(())
+    }
+
+    /// Update filter with new measurements, adding `C^T * R^-1 * C` to the information matrix
+    /// and `C^T * R^-1 * z` to the information vector. Updates of independent sensors commute.
+    ///
+    /// Returns `KalmanError::NonPositiveDefiniteNoise` and leaves the estimate unchanged if
+    /// `R` is not positive-definite.
+    pub fn update<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        z: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> Result<(), KalmanError> {
+        let chol = R.cholesky().ok_or(KalmanError::NonPositiveDefiniteNoise)?;
+        self.Y += C.transpose() * chol.solve(C);
+        self.y += C.transpose() * chol.solve(z);
+        Ok(())
+    }
+
+    /// Get state vector `x`, solved from `Y*x = y`.
+    /// Returns `None` if the information matrix is not positive-definite.
+    pub fn get_state(&self) -> Option<SMatrix<F, Nx, 1>> {
+        Some(self.Y.cholesky()?.solve(&self.y))
+    }
+
+    /// Get covariance matrix `P = Y^-1`.
+    /// Returns `None` if the information matrix is not positive-definite.
+    pub fn get_covariance(&self) -> Option<SMatrix<F, Nx, Nx>> {
+        Some(self.Y.cholesky()?.inverse())
+    }
+
+    /// Get the information vector `y` and the information matrix `Y`.
+    pub fn get_information(&self) -> (SMatrix<F, Nx, 1>, SMatrix<F, Nx, Nx>) {
+        (self.y, self.Y)
+    }
+}
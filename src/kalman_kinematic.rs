@@ -0,0 +1,124 @@
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use nalgebra::{matrix, RealField, SMatrix};
+
+use crate::kalman::{self, KalmanError};
+
+/// Constant-velocity kinematic filter over the state `[position, velocity]`, driven by
+/// continuous white-noise acceleration with the given power spectral density. The optional
+/// input is a known acceleration.
+pub struct ConstantVelocity<F: RealField + Copy> {
+
+    // Filter over the state `[position, velocity]`
+    filter: kalman::KalmanFilter<2, 1, F>,
+
+}
+
+impl<F: RealField + Copy> ConstantVelocity<F> {
+    /// Provide kinematic filter with time step `dt`, acceleration noise density `q` and initial values
+    pub fn new(dt: F, q: F, x_init: SMatrix<F, 2, 1>, P_init: SMatrix<F, 2, 2>) -> Self {
+        let dt2 = dt * dt;
+        let mut filter = kalman::KalmanFilter::constant_velocity(dt, q);
+        filter.set_B(Some(matrix![dt2 * nalgebra::convert(0.5); dt]));
+        filter.reset(x_init, P_init);
+        Self { filter }
+    }
+
+    /// Predict new state, assuming zero acceleration.
+    pub fn predict(&mut self) {
+        self.filter.predict()
+    }
+
+    /// Predict new state using a known acceleration.
+    pub fn predict_with_input(&mut self, acceleration: F) {
+        self.filter.predict_with_input(matrix![acceleration])
+    }
+
+    /// Update filter with a position measurement of the given variance.
+    pub fn update_position(&mut self, position: F, variance: F) -> Result<(), KalmanError> {
+        self.filter.update(&matrix![F::one(), F::zero()], &matrix![variance], &matrix![position])
+    }
+
+    /// Get the estimated position.
+    pub fn position(&self) -> F {
+        self.filter.get_state()[0]
+    }
+
+    /// Get the estimated velocity.
+    pub fn velocity(&self) -> F {
+        self.filter.get_state()[1]
+    }
+
+    /// Get the underlying filter.
+    pub fn filter(&self) -> &kalman::KalmanFilter<2, 1, F> {
+        &self.filter
+    }
+
+    /// Get the underlying filter mutably, such as for other measurement models.
+    pub fn filter_mut(&mut self) -> &mut kalman::KalmanFilter<2, 1, F> {
+        &mut self.filter
+    }
+}
+
+/// Constant-acceleration kinematic filter over the state `[position, velocity, acceleration]`,
+/// driven by continuous white-noise jerk with the given power spectral density. The optional
+/// input is a known jerk.
+pub struct ConstantAcceleration<F: RealField + Copy> {
+
+    // Filter over the state `[position, velocity, acceleration]`
+    filter: kalman::KalmanFilter<3, 1, F>,
+
+}
+
+impl<F: RealField + Copy> ConstantAcceleration<F> {
+    /// Provide kinematic filter with time step `dt`, jerk noise density `q` and initial values
+    pub fn new(dt: F, q: F, x_init: SMatrix<F, 3, 1>, P_init: SMatrix<F, 3, 3>) -> Self {
+        let c = |v: f64| -> F { nalgebra::convert(v) };
+        let (dt2, dt3) = (dt * dt, dt * dt * dt);
+        let mut filter = kalman::KalmanFilter::constant_acceleration(dt, q);
+        filter.set_B(Some(matrix![dt3 / c(6.0); dt2 * c(0.5); dt]));
+        filter.reset(x_init, P_init);
+        Self { filter }
+    }
+
+    /// Predict new state, assuming zero jerk.
+    pub fn predict(&mut self) {
+        self.filter.predict()
+    }
+
+    /// Predict new state using a known jerk.
+    pub fn predict_with_input(&mut self, jerk: F) {
+        self.filter.predict_with_input(matrix![jerk])
+    }
+
+    /// Update filter with a position measurement of the given variance.
+    pub fn update_position(&mut self, position: F, variance: F) -> Result<(), KalmanError> {
+        self.filter.update(&matrix![F::one(), F::zero(), F::zero()], &matrix![variance], &matrix![position])
+    }
+
+    /// Get the estimated position.
+    pub fn position(&self) -> F {
+        self.filter.get_state()[0]
+    }
+
+    /// Get the estimated velocity.
+    pub fn velocity(&self) -> F {
+        self.filter.get_state()[1]
+    }
+
+    /// Get the estimated acceleration.
+    pub fn acceleration(&self) -> F {
+        self.filter.get_state()[2]
+    }
+
+    /// Get the underlying filter.
+    pub fn filter(&self) -> &kalman::KalmanFilter<3, 1, F> {
+        &self.filter
+    }
+
+    /// Get the underlying filter mutably, such as for other measurement models.
+    pub fn filter_mut(&mut self) -> &mut kalman::KalmanFilter<3, 1, F> {
+        &mut self.filter
+    }
+}
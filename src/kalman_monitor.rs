@@ -0,0 +1,195 @@
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use nalgebra::{RealField, SMatrix};
+
+use crate::kalman::{KalmanError, KalmanFilter};
+
+/// Fixed-capacity window holding the last `W` recorded values.
+struct RollingWindow<const W: usize, F: RealField + Copy> {
+    values: [F; W],
+    next: usize,
+}
+
+impl<const W: usize, F: RealField + Copy> RollingWindow<W, F> {
+    fn new() -> Self {
+        Self {
+            values: [F::zero(); W],
+            next: 0,
+        }
+    }
+
+    /// Push a new value, overwriting the oldest one once the window is full.
+    fn push(&mut self, value: F) {
+        if W == 0 { return }
+        self.values[self.next] = value;
+        self.next = (self.next + 1) % W;
+    }
+
+    /// Sum of all values in the window.
+    fn sum(&self) -> F {
+        self.values.iter().fold(F::zero(), |acc, v| acc + *v)
+    }
+}
+
+/// Tracks the information gained by a filter over the last `W` recorded steps,
+/// measured as the reduction of the log-determinant of the covariance `P`.
+pub struct InformationGainWindow<const W: usize, F: RealField + Copy> {
+
+    // Per-step log-det reductions
+    window: RollingWindow<W, F>,
+
+    // Log-det of the covariance at the previous record
+    last: Option<F>,
+
+}
+
+impl<const W: usize, F: RealField + Copy> InformationGainWindow<W, F> {
+    /// Provide an empty window, where the first record only sets the reference covariance.
+    pub fn new() -> Self {
+        Self {
+            window: RollingWindow::new(),
+            last: None,
+        }
+    }
+
+    /// Record the current covariance of the filter, typically once per predict/update cycle.
+    /// Steps where the uncertainty grew, such as pure predictions, count as zero gain.
+    pub fn record<const Nx: usize, const Nu: usize>(&mut self, filter: &KalmanFilter<Nx, Nu, F>) {
+        let Some(log_det) = filter.covariance_log_determinant() else { return };
+        if let Some(last) = self.last {
+            self.window.push((last - log_det).max(F::zero()));
+        }
+        self.last = Some(log_det);
+    }
+
+    /// Total log-det reduction of the covariance over the last `W` recorded steps.
+    pub fn window_information_gain(&self) -> F {
+        self.window.sum()
+    }
+}
+
+impl<const W: usize, F: RealField + Copy> Default for InformationGainWindow<W, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Tracks the sensitivity of a filter estimate to the measurements of the last `W` recorded
+/// steps, measured as the sum of the Frobenius norms of the Kalman gains of the updates.
+pub struct SensitivityWindow<const W: usize, F: RealField + Copy> {
+
+    // Per-step gain norms
+    window: RollingWindow<W, F>,
+
+    // Cumulative gain norm of the filter at the previous record
+    last: Option<F>,
+
+}
+
+impl<const W: usize, F: RealField + Copy> SensitivityWindow<W, F> {
+    /// Provide an empty window, where the first record only sets the reference cumulative gain norm.
+    pub fn new() -> Self {
+        Self {
+            window: RollingWindow::new(),
+            last: None,
+        }
+    }
+
+    /// Record the gains of the filter since the previous record, typically once per predict/update cycle.
+    pub fn record<const Nx: usize, const Nu: usize>(&mut self, filter: &KalmanFilter<Nx, Nu, F>) {
+        let cumulative = filter.cumulative_gain_norm();
+        if let Some(last) = self.last {
+            self.window.push(cumulative - last);
+        }
+        self.last = Some(cumulative);
+    }
+
+    /// Total gain norm of the measurements over the last `W` recorded steps.
+    pub fn recent_measurement_sensitivity(&self) -> F {
+        self.window.sum()
+    }
+}
+
+impl<const W: usize, F: RealField + Copy> Default for SensitivityWindow<W, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Running innovation statistics of a registered sensor.
+struct SensorStats<F: RealField + Copy> {
+    name: &'static str,
+    sum_squares: F,
+    count: usize,
+}
+
+/// Sensor health monitor for up to `S` named sensors, maintaining the running RMS of the
+/// standardized innovations of each sensor. A sensor whose RMS drifts well above one is
+/// inconsistent with the filter, such as a biased or degraded sensor.
+pub struct SensorHealth<const S: usize, F: RealField + Copy> {
+
+    // Statistics of the registered sensors
+    sensors: [Option<SensorStats<F>>; S],
+
+}
+
+impl<const S: usize, F: RealField + Copy> SensorHealth<S, F> {
+    /// Provide a monitor without registered sensors.
+    pub fn new() -> Self {
+        Self {
+            sensors: core::array::from_fn(|_| None),
+        }
+    }
+
+    /// Register a named sensor. Returns `false` if all `S` slots are taken
+    /// by other sensors. Registering an existing sensor has no effect.
+    pub fn register(&mut self, name: &'static str) -> bool {
+        if self.find(name).is_some() {
+            return true;
+        }
+        let Some(slot) = self.sensors.iter_mut().find(|slot| slot.is_none()) else { return false };
+        *slot = Some(SensorStats { name, sum_squares: F::zero(), count: 0 });
+        true
+    }
+
+    /// Record the standardized innovation of a measurement of the named sensor against
+    /// the current estimate of the filter, and update the filter with the measurement.
+    /// Returns `Ok(false)` without updating the filter if the sensor is not registered, and
+    /// records nothing if the update fails.
+    pub fn update<const Nx: usize, const Nu: usize, const Ny: usize>(
+        &mut self,
+        filter: &mut KalmanFilter<Nx, Nu, F>,
+        name: &'static str,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> Result<bool, KalmanError> {
+        let Some(stats) = self.find(name) else { return Ok(false) };
+        let z = filter.standardize_innovation(C, R, y);
+        filter.update(C, R, y)?;
+        stats.sum_squares += z.norm_squared();
+        stats.count += Ny;
+        Ok(true)
+    }
+
+    /// Get the RMS of the standardized innovation components of the named sensor,
+    /// or `None` if it is not registered or has no recorded measurements.
+    pub fn sensor_innovation_rms(&self, name: &str) -> Option<F> {
+        let stats = self.sensors.iter().flatten().find(|stats| stats.name == name)?;
+        if stats.count == 0 {
+            return None;
+        }
+        Some((stats.sum_squares / nalgebra::convert(stats.count as f64)).sqrt())
+    }
+
+    fn find(&mut self, name: &str) -> Option<&mut SensorStats<F>> {
+        self.sensors.iter_mut().flatten().find(|stats| stats.name == name)
+    }
+}
+
+impl<const S: usize, F: RealField + Copy> Default for SensorHealth<S, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,119 @@
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use nalgebra::{RealField, SMatrix};
+
+use crate::kalman::{KalmanError, VecMat};
+
+/// Extended `Nx`-dimensional Kalman filter with nonlinear models supplied as closures.
+///
+/// The state-transition and measurement functions, along with their Jacobians, are passed to
+/// each prediction and update, such that they can capture their parameters and such that
+/// measurements of different dimensions and models can be fused into the same filter.
+pub struct KalmanFilter<const Nx: usize, const Nu: usize, F: RealField + Copy> {
+
+    // Model noise covariance matrix
+    Q: SMatrix<F, Nx, Nx>,
+
+    // A priori state vector and covariance matrix
+    prio: VecMat<Nx, F>,
+
+    // A posteriori state vector and covariance matrix
+    post: Option<VecMat<Nx, F>>,
+
+}
+
+impl<const Nx: usize, const Nu: usize, F: RealField + Copy> KalmanFilter<Nx, Nu, F> {
+    /// Provide kalman filter with the model noise covariance and initial values
+    pub fn new(
+        Q: SMatrix<F, Nx, Nx>,
+        x_init: SMatrix<F, Nx, 1>,
+        P_init: SMatrix<F, Nx, Nx>,
+    ) -> Self {
+        Self {
+            Q,
+            prio: VecMat {
+                x: x_init,
+                P: P_init,
+            },
+            post: None,
+        }
+    }
+
+    /// Predict new state with the state-transition function `x[k+1] = f(x[k])` and its
+    /// Jacobian `F_jac` with respect to the state.
+    pub fn predict(
+        &mut self,
+        f: impl Fn(&SMatrix<F, Nx, 1>) -> SMatrix<F, Nx, 1>,
+        F_jac: impl Fn(&SMatrix<F, Nx, 1>) -> SMatrix<F, Nx, Nx>,
+    ) {
+        self.predict_with_input(|x, _| f(x), F_jac, &SMatrix::zeros())
+    }
+
+    /// Predict new state using input, with the state-transition function `x[k+1] = f(x[k], u[k])`
+    /// and its Jacobian `F_jac` with respect to the state. The Jacobian is evaluated at the
+    /// current estimate, and takes the place of `A` in the covariance propagation.
+    pub fn predict_with_input(
+        &mut self,
+        f: impl Fn(&SMatrix<F, Nx, 1>, &SMatrix<F, Nu, 1>) -> SMatrix<F, Nx, 1>,
+        F_jac: impl Fn(&SMatrix<F, Nx, 1>) -> SMatrix<F, Nx, Nx>,
+        u: &SMatrix<F, Nu, 1>,
+    ) {
+        let current = self.post.take().unwrap_or_else(|| self.prio.clone());
+
+        // Symmetrize
+        let P = (current.P + current.P.transpose()).scale(nalgebra::convert(0.5));
+
+        // Update priors
+        let Fj = F_jac(&current.x);
+        self.prio = VecMat {
+            x: f(&current.x, u),
+            P: Fj * P * Fj.transpose() + self.Q,
+        };
+    }
+
+    /// Update filter with a measurement `y = h(x) + v` with covariance `R`, where `H` is the Jacobian
+    /// of `h` with respect to the state. The measurement is linearized about the current estimate,
+    /// with the Jacobian taking the place of `C`, such that sequential updates compose.
+    ///
+    /// Returns `KalmanError::SingularInnovation` and leaves the estimate unchanged if the
+    /// innovation covariance `S` cannot be inverted.
+    pub fn update<const Ny: usize>(
+        &mut self,
+        h: impl Fn(&SMatrix<F, Nx, 1>) -> SMatrix<F, Ny, 1>,
+        H: impl Fn(&SMatrix<F, Nx, 1>) -> SMatrix<F, Ny, Nx>,
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> Result<(), KalmanError> {
+        let current = self.post.as_ref().unwrap_or(&self.prio);
+
+        // Measurement prediction residual and innovation (or pre-fit residual) covariance
+        let y_res = y - h(&current.x);
+        let Hj = H(&current.x);
+        let S = Hj * current.P * Hj.transpose() + R;
+
+        // Optimal Kalman gain
+        let Sinv = S.try_inverse().ok_or(KalmanError::SingularInnovation)?;
+        let K = current.P * Hj.transpose() * Sinv;
+
+        // Updated (a posteriori) estimate and symmetrized covariance
+        let P = (SMatrix::identity() - K * Hj) * current.P;
+        self.post = Some(VecMat {
+            x: current.x + K * y_res,
+            P: (P + P.transpose()).scale(nalgebra::convert(0.5)),
+        });
+        Ok(())
+    }
+
+    /// Get state vector `x`.
+    /// Returns the posterior estimate if it exists, otherwise returns priori prediction.
+    pub fn get_state(&self) -> SMatrix<F, Nx, 1> {
+        self.post.as_ref().unwrap_or(&self.prio).x
+    }
+
+    /// Get covariance matrix `P`.
+    /// Returns the posterior covariance if it exists, otherwise returns priori covariance.
+    pub fn get_covariance(&self) -> SMatrix<F, Nx, Nx> {
+        self.post.as_ref().unwrap_or(&self.prio).P
+    }
+}
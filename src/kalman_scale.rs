@@ -0,0 +1,116 @@
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use nalgebra::{RealField, SMatrix};
+
+use crate::kalman::{self, KalmanError};
+
+/// Linear `Nx`-dimensional Kalman filter with an augmented multiplicative measurement scale
+/// factor state `s`, for sensors with an unknown gain.
+///
+/// The scale factor follows random-walk dynamics `s[k+1] = s[k] + w[k]`, and the scaled
+/// measurement `y = s*C*x + v` is fused with an extended Kalman filter update of the bilinear
+/// term. `Na` is the augmented dimension and must equal `Nx + 1`. Note that the scale factor is
+/// only observable alongside the states when some other measurement constrains the states.
+pub struct KalmanFilter<const Nx: usize, const Nu: usize, const Na: usize, F: RealField + Copy> {
+
+    // Augmented filter over the state vector `[x, s]`
+    filter: kalman::KalmanFilter<Na, Nu, F>,
+
+}
+
+impl<const Nx: usize, const Nu: usize, const Na: usize, F: RealField + Copy> KalmanFilter<Nx, Nu, Na, F> {
+    /// Provide kalman filter with the base model, scale factor random-walk variance `q_scale`
+    /// and initial scale factor variance `p_scale`. The scale factor is initialized to one.
+    pub fn new(
+        A: SMatrix<F, Nx, Nx>,
+        B: Option<SMatrix<F, Nx, Nu>>,
+        Q: SMatrix<F, Nx, Nx>,
+        q_scale: F,
+        x_init: SMatrix<F, Nx, 1>,
+        P_init: SMatrix<F, Nx, Nx>,
+        p_scale: F,
+    ) -> Self {
+        const { assert!(Na == Nx + 1, "augmented dimension `Na` must equal `Nx + 1`") };
+
+        // Base dynamics on the first block, random walk on the scale factor
+        let mut A_a = SMatrix::<F, Na, Na>::identity();
+        A_a.fixed_view_mut::<Nx, Nx>(0, 0).copy_from(&A);
+
+        let mut B_a = SMatrix::<F, Na, Nu>::zeros();
+        if let Some(B) = B {
+            B_a.fixed_view_mut::<Nx, Nu>(0, 0).copy_from(&B);
+        }
+
+        let mut Q_a = SMatrix::<F, Na, Na>::zeros();
+        Q_a.fixed_view_mut::<Nx, Nx>(0, 0).copy_from(&Q);
+        Q_a[(Nx, Nx)] = q_scale;
+
+        let mut x_a = SMatrix::<F, Na, 1>::zeros();
+        x_a.fixed_view_mut::<Nx, 1>(0, 0).copy_from(&x_init);
+        x_a[Nx] = F::one();
+
+        let mut P_a = SMatrix::<F, Na, Na>::zeros();
+        P_a.fixed_view_mut::<Nx, Nx>(0, 0).copy_from(&P_init);
+        P_a[(Nx, Nx)] = p_scale;
+
+        Self {
+            filter: kalman::KalmanFilter::new(A_a, Some(B_a), Q_a, x_a, P_a),
+        }
+    }
+
+    /// Predict new state. The scale factor estimate is carried over unchanged.
+    pub fn predict(&mut self) {
+        self.filter.predict()
+    }
+
+    /// Predict new state using input. The scale factor estimate is carried over unchanged.
+    pub fn predict_with_input(&mut self, u: SMatrix<F, Nu, 1>) {
+        self.filter.predict_with_input(u)
+    }
+
+    /// Update filter with a scaled measurement `y = s*C*x + v`, linearized about the current estimate.
+    pub fn update<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> Result<(), KalmanError> {
+        let x = self.get_state();
+        let s = self.get_scale_estimate();
+
+        // Jacobian of `s*C*x` with respect to `[x, s]`
+        let mut H = SMatrix::<F, Ny, Na>::zeros();
+        H.fixed_view_mut::<Ny, Nx>(0, 0).copy_from(&(C * s));
+        H.fixed_view_mut::<Ny, 1>(0, Nx).copy_from(&(C * x));
+
+        self.filter.update_linearized(&H, R, &(y - C * x * s))
+    }
+
+    /// Update filter with an unscaled measurement `y = C*x + v`, such as from a reference sensor.
+    pub fn update_unscaled<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> Result<(), KalmanError> {
+        let mut C_a = SMatrix::<F, Ny, Na>::zeros();
+        C_a.fixed_view_mut::<Ny, Nx>(0, 0).copy_from(C);
+        self.filter.update(&C_a, R, y)
+    }
+
+    /// Get base state vector `x`, without the scale factor state.
+    pub fn get_state(&self) -> SMatrix<F, Nx, 1> {
+        self.filter.get_state().fixed_rows::<Nx>(0).into_owned()
+    }
+
+    /// Get the estimated measurement scale factor `s`.
+    pub fn get_scale_estimate(&self) -> F {
+        self.filter.get_state()[Nx]
+    }
+
+    /// Get the underlying augmented filter over `[x, s]`.
+    pub fn augmented(&self) -> &kalman::KalmanFilter<Na, Nu, F> {
+        &self.filter
+    }
+}
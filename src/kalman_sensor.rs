@@ -0,0 +1,80 @@
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use nalgebra::{RealField, SMatrix};
+
+use crate::kalman::{KalmanError, KalmanFilter};
+
+/// Wrapper of a linear Kalman filter with a stored measurement model `C`, `R` of a fixed
+/// `Ny`-dimensional sensor, such that updates only need the measurement vector. Measurements
+/// of other sensors can still be fused with explicit models through `.update`.
+pub struct SensorFilter<const Nx: usize, const Nu: usize, const Ny: usize, F: RealField + Copy> {
+
+    // Wrapped filter
+    filter: KalmanFilter<Nx, Nu, F>,
+
+    // Output matrix of the sensor
+    C: SMatrix<F, Ny, Nx>,
+
+    // Measurement noise covariance matrix of the sensor
+    R: SMatrix<F, Ny, Ny>,
+
+}
+
+impl<const Nx: usize, const Nu: usize, const Ny: usize, F: RealField + Copy> SensorFilter<Nx, Nu, Ny, F> {
+    /// Wrap a filter with the measurement model `C`, `R` of the sensor.
+    pub fn new(filter: KalmanFilter<Nx, Nu, F>, C: SMatrix<F, Ny, Nx>, R: SMatrix<F, Ny, Ny>) -> Self {
+        Self { filter, C, R }
+    }
+
+    /// Replace the stored measurement model of the sensor, such as after recalibration.
+    pub fn set_measurement_model(&mut self, C: SMatrix<F, Ny, Nx>, R: SMatrix<F, Ny, Ny>) {
+        self.C = C;
+        self.R = R;
+    }
+
+    /// Predict new state.
+    pub fn predict(&mut self) {
+        self.filter.predict()
+    }
+
+    /// Predict new state using input.
+    pub fn predict_with_input(&mut self, u: SMatrix<F, Nu, 1>) {
+        self.filter.predict_with_input(u)
+    }
+
+    /// Update filter with a new measurement of the sensor, using the stored measurement model.
+    pub fn update_with(&mut self, y: &SMatrix<F, Ny, 1>) -> Result<(), KalmanError> {
+        self.filter.update(&self.C, &self.R, y)
+    }
+
+    /// Update filter with new measurements of another sensor.
+    pub fn update<const Nz: usize>(
+        &mut self,
+        C: &SMatrix<F, Nz, Nx>, // Output matrix
+        R: &SMatrix<F, Nz, Nz>, // Covariance
+        y: &SMatrix<F, Nz, 1>,  // Measurement
+    ) -> Result<(), KalmanError> {
+        self.filter.update(C, R, y)
+    }
+
+    /// Get state vector `x`.
+    pub fn get_state(&self) -> SMatrix<F, Nx, 1> {
+        self.filter.get_state()
+    }
+
+    /// Get the stored measurement model `(C, R)` of the sensor.
+    pub fn get_measurement_model(&self) -> (SMatrix<F, Ny, Nx>, SMatrix<F, Ny, Ny>) {
+        (self.C, self.R)
+    }
+
+    /// Get the underlying filter.
+    pub fn filter(&self) -> &KalmanFilter<Nx, Nu, F> {
+        &self.filter
+    }
+
+    /// Get the underlying filter mutably.
+    pub fn filter_mut(&mut self) -> &mut KalmanFilter<Nx, Nu, F> {
+        &mut self.filter
+    }
+}
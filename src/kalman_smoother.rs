@@ -0,0 +1,175 @@
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use nalgebra::{RealField, SMatrix};
+
+use crate::kalman::KalmanFilter;
+
+/// Estimates of a single recorded step of the forward filter.
+#[derive(Clone, Copy)]
+struct RecordedStep<const Nx: usize, F: RealField + Copy> {
+    // Prior (predicted) estimate of the step
+    x_prio: SMatrix<F, Nx, 1>,
+    P_prio: SMatrix<F, Nx, Nx>,
+
+    // Filtered estimate of the step, after any updates
+    x: SMatrix<F, Nx, 1>,
+    P: SMatrix<F, Nx, Nx>,
+
+    // Model propagation matrix of the prediction into the step, as left on the filter by it
+    A: SMatrix<F, Nx, Nx>,
+}
+
+impl<const Nx: usize, F: RealField + Copy> RecordedStep<Nx, F> {
+    /// Record the current step of the forward filter.
+    fn new<const Nu: usize>(filter: &KalmanFilter<Nx, Nu, F>) -> Self {
+        let (x_prio, P_prio) = filter.prior();
+        Self {
+            x_prio,
+            P_prio,
+            x: filter.get_state(),
+            P: filter.get_covariance(),
+            A: *filter.transition(),
+        }
+    }
+
+    /// Smoothed estimate of this step from the smoothed estimate `(x_next, P_next)` of the
+    /// `next` step, using the RTS recursion. Returns `None` if the predicted covariance of
+    /// the next step is not positive-definite.
+    fn smooth(
+        &self,
+        next: &Self,
+        x_next: &SMatrix<F, Nx, 1>,
+        P_next: &SMatrix<F, Nx, Nx>,
+    ) -> Option<(SMatrix<F, Nx, 1>, SMatrix<F, Nx, Nx>)> {
+        // Smoother gain `G = P * A^T * P_prio^-1` of the prediction into the next step
+        let G = next.P_prio.cholesky()?.solve(&(next.A * self.P)).transpose();
+        let x = self.x + G * (x_next - next.x_prio);
+        let P = self.P + G * (P_next - next.P_prio) * G.transpose();
+        Some((x, (P + P.transpose()).scale(nalgebra::convert(0.5))))
+    }
+}
+
+/// Rauch-Tung-Striebel smoother over a recorded run of a linear Kalman filter, giving
+/// estimates of every step that use all measurements of the run, including future ones.
+#[cfg(feature = "alloc")]
+pub struct KalmanSmoother<const Nx: usize, F: RealField + Copy> {
+
+    // Recorded steps of the forward filter, in order
+    steps: Vec<RecordedStep<Nx, F>>,
+
+}
+
+#[cfg(feature = "alloc")]
+impl<const Nx: usize, F: RealField + Copy> Default for KalmanSmoother<Nx, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const Nx: usize, F: RealField + Copy> KalmanSmoother<Nx, F> {
+    /// Create a smoother without recorded steps.
+    pub fn new() -> Self {
+        Self { steps: Vec::new() }
+    }
+
+    /// Record the current step of the forward filter, after its updates and before its next
+    /// prediction. The model propagation matrix `A` used by the prediction into the step is
+    /// recorded with it, such that changes made with `.set_A` or `.predict_dt` before each
+    /// prediction are accounted for.
+    pub fn record_step<const Nu: usize>(&mut self, filter: &KalmanFilter<Nx, Nu, F>) {
+        self.steps.push(RecordedStep::new(filter));
+    }
+
+    /// Get the number of recorded steps.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Check whether no steps have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Run the backward RTS recursion over the recorded steps, giving the smoothed mean and
+    /// covariance `(x, P)` of each step in order. The last step equals the filtered estimate.
+    /// Returns `None` if any predicted covariance is not positive-definite.
+    pub fn smooth(&self) -> Option<Vec<(SMatrix<F, Nx, 1>, SMatrix<F, Nx, Nx>)>> {
+        let Some(last) = self.steps.last() else { return Some(Vec::new()) };
+        let mut smoothed = Vec::with_capacity(self.steps.len());
+        smoothed.push((last.x, last.P));
+
+        for (step, next) in self.steps.iter().rev().skip(1).zip(self.steps.iter().rev()) {
+            let (x_next, P_next) = smoothed[smoothed.len() - 1];
+            smoothed.push(step.smooth(next, &x_next, &P_next)?);
+        }
+
+        smoothed.reverse();
+        Some(smoothed)
+    }
+}
+
+/// Fixed-lag smoother, giving the smoothed estimate of the step `L` steps in the past using
+/// the measurements of the `L` steps since. Only the last `L` steps are buffered, so memory
+/// and the work per step are bounded, and no allocation is needed.
+pub struct FixedLagSmoother<const Nx: usize, const L: usize, F: RealField + Copy> {
+
+    // Ring buffer of the previous `L` steps of the forward filter
+    steps: [Option<RecordedStep<Nx, F>>; L],
+
+    // Index of the oldest buffered step, which is overwritten next
+    oldest: usize,
+
+}
+
+impl<const Nx: usize, const L: usize, F: RealField + Copy> Default for FixedLagSmoother<Nx, L, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const Nx: usize, const L: usize, F: RealField + Copy> FixedLagSmoother<Nx, L, F> {
+    /// Create a smoother without buffered steps.
+    pub fn new() -> Self {
+        Self { steps: [None; L], oldest: 0 }
+    }
+
+    /// Record the current step of the forward filter, after its updates and before its next
+    /// prediction, returning the smoothed mean and covariance `(x, P)` of the step `L` steps
+    /// before it. Until `L` previous steps are buffered, the filtered estimate of the current
+    /// step is returned instead. Returns `None` if any buffered predicted covariance is not
+    /// positive-definite, in which case the step is still recorded.
+    pub fn record_step<const Nu: usize>(
+        &mut self,
+        filter: &KalmanFilter<Nx, Nu, F>,
+    ) -> Option<(SMatrix<F, Nx, 1>, SMatrix<F, Nx, Nx>)> {
+        let current = RecordedStep::new(filter);
+        let smoothed = if self.steps.iter().all(Option::is_some) {
+            self.smooth_lagged(&current)
+        } else {
+            Some((current.x, current.P))
+        };
+
+        if L > 0 {
+            self.steps[self.oldest] = Some(current);
+            self.oldest = (self.oldest + 1) % L;
+        }
+        smoothed
+    }
+
+    /// Run the backward RTS recursion from the `current` step through the full buffer.
+    fn smooth_lagged(&self, current: &RecordedStep<Nx, F>) -> Option<(SMatrix<F, Nx, 1>, SMatrix<F, Nx, Nx>)> {
+        let (mut x, mut P) = (current.x, current.P);
+        let mut next = current;
+        for i in 1..=L {
+            // Newest to oldest
+            let step = self.steps[(self.oldest + L - i) % L].as_ref()?;
+            (x, P) = step.smooth(next, &x, &P)?;
+            next = step;
+        }
+        Some((x, P))
+    }
+}
@@ -0,0 +1,109 @@
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use nalgebra::{RealField, SMatrix};
+
+use crate::kalman::{self, KalmanError};
+use crate::linalg;
+
+/// Square-root `Nx`-dimensional Kalman filter, propagating a square-root factor `S` of the
+/// covariance `P = S*S^T` rather than `P` itself.
+///
+/// The covariance is positive semi-definite by construction, regardless of conditioning and
+/// roundoff, such as for `f32` filters with very precise measurements.
+pub struct KalmanFilter<const Nx: usize, const Nu: usize, F: RealField + Copy> {
+
+    // Model propagation matrix
+    A: SMatrix<F, Nx, Nx>,
+
+    // Input matrix
+    B: SMatrix<F, Nx, Nu>,
+
+    // Square-root factor of the model noise covariance matrix
+    Q_sqrt: SMatrix<F, Nx, Nx>,
+
+    // State vector and covariance factor of the current estimate
+    x: SMatrix<F, Nx, 1>,
+    S: SMatrix<F, Nx, Nx>,
+
+}
+
+impl<const Nx: usize, const Nu: usize, F: RealField + Copy> KalmanFilter<Nx, Nu, F> {
+    /// Provide kalman filter with all initial values. The covariance matrices `Q` and `P_init`
+    /// must be positive semi-definite, but need not be positive-definite.
+    pub fn new(
+        A: SMatrix<F, Nx, Nx>,
+        B: Option<SMatrix<F, Nx, Nu>>,
+        Q: SMatrix<F, Nx, Nx>,
+        x_init: SMatrix<F, Nx, 1>,
+        P_init: SMatrix<F, Nx, Nx>,
+    ) -> Self {
+        Self {
+            A,
+            B: B.unwrap_or_else(SMatrix::zeros),
+            Q_sqrt: linalg::symmetric_square_root(&Q),
+            x: x_init,
+            S: linalg::symmetric_square_root(&P_init),
+        }
+    }
+
+    /// Predict new state. If plant dynamics are time-dependent,
+    /// this method (or `.predict_with_input`) must be called at the correct frequency.
+    pub fn predict(&mut self) {
+        self.predict_with_input(SMatrix::zeros())
+    }
+
+    /// Predict new state using input. If plant dynamics are time-dependent,
+    /// this method (or `.predict`) must be called at the correct frequency.
+    ///
+    /// The predicted factor is the lower triangular factor of `[A*S, Q_sqrt]`, computed by
+    /// a QR decomposition of its transpose.
+    pub fn predict_with_input(&mut self, u: SMatrix<F, Nu, 1>) {
+        self.x = self.A * self.x + self.B * u;
+        let R = linalg::stacked_triangular_factor(&(self.A * self.S).transpose(), &self.Q_sqrt.transpose());
+        self.S = R.transpose();
+    }
+
+    /// Update filter with new measurements. The measurement is prewhitened, and each whitened
+    /// component is applied as a rank-one downdate of the covariance factor (Potter's method).
+    ///
+    /// Returns `KalmanError::NonPositiveDefiniteNoise` and leaves the estimate unchanged if
+    /// `R` is not positive-definite.
+    pub fn update<const Ny: usize>(
+        &mut self,
+        C: &SMatrix<F, Ny, Nx>, // Output matrix
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> Result<(), KalmanError> {
+        let (C, y) = kalman::prewhiten(C, R, y).ok_or(KalmanError::NonPositiveDefiniteNoise)?;
+
+        for i in 0..Ny {
+            let c = C.row(i);
+
+            // Scalar measurement with unit variance
+            let phi = self.S.transpose() * c.transpose();
+            let alpha = F::one() / (phi.norm_squared() + F::one());
+            let gamma = F::one() / (F::one() + alpha.sqrt());
+            let K = self.S * phi * alpha;
+
+            self.x += K * (y[i] - (c * self.x)[0]);
+            self.S -= K * phi.transpose() * gamma;
+        }
+        Ok(())
+    }
+
+    /// Get state vector `x`.
+    pub fn get_state(&self) -> SMatrix<F, Nx, 1> {
+        self.x
+    }
+
+    /// Get covariance matrix `P`, reconstructed as `S*S^T`.
+    pub fn get_covariance(&self) -> SMatrix<F, Nx, Nx> {
+        self.S * self.S.transpose()
+    }
+
+    /// Get the square-root factor `S` of the covariance matrix `P = S*S^T`.
+    pub fn get_covariance_factor(&self) -> SMatrix<F, Nx, Nx> {
+        self.S
+    }
+}
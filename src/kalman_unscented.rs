@@ -0,0 +1,175 @@
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use nalgebra::{RealField, SMatrix};
+
+use crate::kalman::{KalmanError, VecMat};
+
+/// Mean and covariance of the unscented transform of an estimate, along with the
+/// cross-covariance between the state and the transformed value.
+struct Transformed<const Nx: usize, const Ny: usize, F: RealField + Copy> {
+    mean: SMatrix<F, Ny, 1>,
+    P: SMatrix<F, Ny, Ny>,
+    Pxy: SMatrix<F, Nx, Ny>,
+}
+
+/// Unscented `Nx`-dimensional Kalman filter with nonlinear models supplied as closures.
+///
+/// The mean and covariance are propagated through the nonlinear models by the `2*Nx + 1`
+/// sigma points of the unscented transform, such that no Jacobians are needed.
+pub struct KalmanFilter<const Nx: usize, const Nu: usize, F: RealField + Copy> {
+
+    // Model noise covariance matrix
+    Q: SMatrix<F, Nx, Nx>,
+
+    // A priori state vector and covariance matrix
+    prio: VecMat<Nx, F>,
+
+    // A posteriori state vector and covariance matrix
+    post: Option<VecMat<Nx, F>>,
+
+    // Spread `Nx + lambda = alpha^2 * (Nx + kappa)` of the sigma points
+    spread: F,
+
+    // Mean and covariance weights of the central sigma point
+    weight_mean: F,
+    weight_covariance: F,
+
+}
+
+impl<const Nx: usize, const Nu: usize, F: RealField + Copy> KalmanFilter<Nx, Nu, F> {
+    /// Provide kalman filter with the model noise covariance, initial values and the parameters
+    /// of the unscented transform. `alpha` sets the spread of the sigma points around the mean,
+    /// `beta` incorporates prior knowledge of the distribution (2 is optimal for Gaussians)
+    /// and `kappa` is a secondary scaling parameter, commonly 0.
+    pub fn new(
+        Q: SMatrix<F, Nx, Nx>,
+        x_init: SMatrix<F, Nx, 1>,
+        P_init: SMatrix<F, Nx, Nx>,
+        alpha: F,
+        beta: F,
+        kappa: F,
+    ) -> Self {
+        let n: F = nalgebra::convert(Nx as f64);
+        let spread = alpha * alpha * (n + kappa);
+
+        // The central mean weight `lambda / (Nx + lambda)` is negative for small `alpha`
+        let weight_mean = (spread - n) / spread;
+        Self {
+            Q,
+            prio: VecMat {
+                x: x_init,
+                P: P_init,
+            },
+            post: None,
+            spread,
+            weight_mean,
+            weight_covariance: weight_mean + F::one() - alpha * alpha + beta,
+        }
+    }
+
+    /// Predict new state with the state-transition function `x[k+1] = f(x[k])`.
+    ///
+    /// Returns `KalmanError::NonPositiveDefiniteCovariance` and leaves the estimate unchanged
+    /// if the sigma points cannot be generated from the covariance.
+    pub fn predict(
+        &mut self,
+        f: impl Fn(&SMatrix<F, Nx, 1>) -> SMatrix<F, Nx, 1>,
+    ) -> Result<(), KalmanError> {
+        self.predict_with_input(|x, _| f(x), &SMatrix::zeros())
+    }
+
+    /// Predict new state using input, with the state-transition function `x[k+1] = f(x[k], u[k])`.
+    ///
+    /// Returns `KalmanError::NonPositiveDefiniteCovariance` and leaves the estimate unchanged
+    /// if the sigma points cannot be generated from the covariance.
+    pub fn predict_with_input(
+        &mut self,
+        f: impl Fn(&SMatrix<F, Nx, 1>, &SMatrix<F, Nu, 1>) -> SMatrix<F, Nx, 1>,
+        u: &SMatrix<F, Nu, 1>,
+    ) -> Result<(), KalmanError> {
+        let predicted = self.transform(|x| f(x, u))?;
+
+        // Update priors and set posteriors to none
+        self.prio = VecMat { x: predicted.mean, P: predicted.P + self.Q };
+        self.post = None;
+        Ok(())
+    }
+
+    /// Update filter with a measurement `y = h(x) + v` with covariance `R`. The sigma points
+    /// are generated from the current estimate, such that sequential updates compose.
+    ///
+    /// Returns an error and leaves the estimate unchanged if the sigma points cannot be
+    /// generated, or if the innovation covariance `S` cannot be inverted.
+    pub fn update<const Ny: usize>(
+        &mut self,
+        h: impl Fn(&SMatrix<F, Nx, 1>) -> SMatrix<F, Ny, 1>,
+        R: &SMatrix<F, Ny, Ny>, // Covariance
+        y: &SMatrix<F, Ny, 1>,  // Measurement
+    ) -> Result<(), KalmanError> {
+        let measured = self.transform(h)?;
+        let S = measured.P + R;
+
+        // Optimal Kalman gain
+        let Sinv = S.try_inverse().ok_or(KalmanError::SingularInnovation)?;
+        let K = measured.Pxy * Sinv;
+
+        // Updated (a posteriori) estimate and symmetrized covariance
+        let current = self.post.as_ref().unwrap_or(&self.prio);
+        let P = current.P - K * S * K.transpose();
+        self.post = Some(VecMat {
+            x: current.x + K * (y - measured.mean),
+            P: (P + P.transpose()).scale(nalgebra::convert(0.5)),
+        });
+        Ok(())
+    }
+
+    /// Get state vector `x`.
+    /// Returns the posterior estimate if it exists, otherwise returns priori prediction.
+    pub fn get_state(&self) -> SMatrix<F, Nx, 1> {
+        self.post.as_ref().unwrap_or(&self.prio).x
+    }
+
+    /// Get covariance matrix `P`.
+    /// Returns the posterior covariance if it exists, otherwise returns priori covariance.
+    pub fn get_covariance(&self) -> SMatrix<F, Nx, Nx> {
+        self.post.as_ref().unwrap_or(&self.prio).P
+    }
+
+    /// Unscented transform of the current estimate through `g`.
+    fn transform<const Ny: usize>(
+        &self,
+        g: impl Fn(&SMatrix<F, Nx, 1>) -> SMatrix<F, Ny, 1>,
+    ) -> Result<Transformed<Nx, Ny, F>, KalmanError> {
+        let current = self.post.as_ref().unwrap_or(&self.prio);
+
+        // Sigma points `x ± L_i` from the columns of the Cholesky factor of the scaled covariance
+        let L = (current.P * self.spread)
+            .cholesky()
+            .ok_or(KalmanError::NonPositiveDefiniteCovariance)?
+            .unpack();
+        let Y_center = g(&current.x);
+        let mut Y_plus: SMatrix<F, Ny, Nx> = SMatrix::zeros();
+        let mut Y_minus = Y_plus;
+        for i in 0..Nx {
+            Y_plus.set_column(i, &g(&(current.x + L.column(i))));
+            Y_minus.set_column(i, &g(&(current.x - L.column(i))));
+        }
+
+        // Weighted mean, with equal weights for all but the central sigma point
+        let weight: F = F::one() / (self.spread + self.spread);
+        let mean = Y_center * self.weight_mean + (Y_plus.column_sum() + Y_minus.column_sum()) * weight;
+
+        // Weighted covariance and cross-covariance
+        let d = Y_center - mean;
+        let mut P = d * d.transpose() * self.weight_covariance;
+        let mut Pxy = SMatrix::zeros();
+        for i in 0..Nx {
+            let d_plus = Y_plus.column(i) - mean;
+            let d_minus = Y_minus.column(i) - mean;
+            P += (d_plus * d_plus.transpose() + d_minus * d_minus.transpose()) * weight;
+            Pxy += (L.column(i) * (d_plus - d_minus).transpose()) * weight;
+        }
+        Ok(Transformed { mean, P, Pxy })
+    }
+}
@@ -1,5 +1,30 @@
 #![no_std]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 pub mod kalman;
 pub mod kalman_extended;
-pub mod kalman_classic;
\ No newline at end of file
+pub mod kalman_classic;
+pub mod kalman_bias;
+pub mod kalman_scale;
+pub mod kalman_monitor;
+pub mod kalman_kinematic;
+pub mod kalman_fixed_gain;
+pub mod kalman_dual_rate;
+pub mod kalman_sensor;
+pub mod kalman_borrowed;
+pub mod kalman_nonlinear;
+pub mod kalman_unscented;
+pub mod kalman_sqrt;
+pub mod kalman_information;
+pub mod kalman_smoother;
+pub mod kalman_imm;
+
+mod linalg;
+
+#[cfg(feature = "alloc")]
+pub mod kalman_batch;
+
+#[cfg(feature = "alloc")]
+pub mod kalman_dynamic;
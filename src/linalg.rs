@@ -0,0 +1,151 @@
+#![allow(non_snake_case)]
+
+use nalgebra::{RealField, SMatrix};
+
+/// Eigenvalues and eigenvectors of the symmetric matrix `M`, using the cyclic Jacobi method.
+/// Column `i` of the returned eigenvector matrix belongs to eigenvalue `i`.
+///
+/// The decompositions provided by `nalgebra` require dimensions known as `typenum` types,
+/// which is not the case for generic const dimensions.
+pub(crate) fn symmetric_eigen<const N: usize, F: RealField + Copy>(
+    M: &SMatrix<F, N, N>,
+) -> (SMatrix<F, N, 1>, SMatrix<F, N, N>) {
+    let half: F = nalgebra::convert(0.5);
+    let mut A = (M + M.transpose()) * half;
+    let mut V = SMatrix::<F, N, N>::identity();
+    let tol = F::default_epsilon() * F::default_epsilon() * A.norm_squared();
+
+    for _ in 0..100 {
+        // Sum of squares of the off-diagonal elements
+        let off = A.norm_squared() - A.diagonal().norm_squared();
+        if off <= tol { break }
+
+        for p in 0..N {
+            for q in p + 1..N {
+                let apq = A[(p, q)];
+                if apq == F::zero() { continue }
+
+                // Rotation annihilating element (p, q)
+                let theta = (A[(q, q)] - A[(p, p)]) / (apq + apq);
+                let t = theta.signum() / (theta.abs() + (theta * theta + F::one()).sqrt());
+                let t = if theta == F::zero() { F::one() } else { t };
+                let c = F::one() / (t * t + F::one()).sqrt();
+                let s = t * c;
+
+                for k in 0..N {
+                    let (akp, akq) = (A[(k, p)], A[(k, q)]);
+                    A[(k, p)] = c * akp - s * akq;
+                    A[(k, q)] = s * akp + c * akq;
+                }
+                for k in 0..N {
+                    let (apk, aqk) = (A[(p, k)], A[(q, k)]);
+                    A[(p, k)] = c * apk - s * aqk;
+                    A[(q, k)] = s * apk + c * aqk;
+                }
+                for k in 0..N {
+                    let (vkp, vkq) = (V[(k, p)], V[(k, q)]);
+                    V[(k, p)] = c * vkp - s * vkq;
+                    V[(k, q)] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+
+    (A.diagonal(), V)
+}
+
+/// Moore-Penrose pseudo-inverse of the symmetric matrix `M`, inverting the eigenvalues above
+/// a relative tolerance and discarding the remaining ones.
+pub(crate) fn symmetric_pseudo_inverse<const N: usize, F: RealField + Copy>(
+    M: &SMatrix<F, N, N>,
+) -> SMatrix<F, N, N> {
+    let (eigenvalues, V) = symmetric_eigen(M);
+    let max = eigenvalues.iter().fold(F::zero(), |acc, e| acc.max(e.abs()));
+    let tol = F::default_epsilon().sqrt() * max;
+    let inverted = eigenvalues.map(|e| if e.abs() > tol { F::one() / e } else { F::zero() });
+    V * SMatrix::from_diagonal(&inverted) * V.transpose()
+}
+
+/// Square-root factor `L` of the symmetric positive semi-definite matrix `M = L*L^T`, from its
+/// eigendecomposition. Unlike the Cholesky factor this exists for singular `M`, but is not
+/// triangular. Negative eigenvalues from roundoff are clamped to zero.
+pub(crate) fn symmetric_square_root<const N: usize, F: RealField + Copy>(
+    M: &SMatrix<F, N, N>,
+) -> SMatrix<F, N, N> {
+    let (eigenvalues, V) = symmetric_eigen(M);
+    V * SMatrix::from_diagonal(&eigenvalues.map(|e| e.max(F::zero()).sqrt()))
+}
+
+/// Upper triangular `R` of the QR decomposition of the stacked matrix `[U; V]`, such that
+/// `R^T*R = U^T*U + V^T*V`, using Givens rotations. The stacked matrix cannot be formed
+/// for generic const dimensions, so the rotations are applied to the two blocks directly.
+pub(crate) fn stacked_triangular_factor<const N: usize, const M: usize, F: RealField + Copy>(
+    U: &SMatrix<F, N, N>,
+    V: &SMatrix<F, M, N>,
+) -> SMatrix<F, N, N> {
+    let mut U = *U;
+    let mut V = *V;
+    for j in 0..N {
+        // Annihilate column `j` below the diagonal of `U`, and all of column `j` of `V`,
+        // by rotating each row against row `j` of `U`
+        for i in j + 1..N + M {
+            let mut row = if i < N { U.row(i).into_owned() } else { V.row(i - N).into_owned() };
+            let (a, b) = (U[(j, j)], row[j]);
+            if b == F::zero() { continue }
+
+            let r = a.hypot(b);
+            let (c, s) = (a / r, b / r);
+            for k in j..N {
+                let (ajk, bk) = (U[(j, k)], row[k]);
+                U[(j, k)] = c * ajk + s * bk;
+                row[k] = c * bk - s * ajk;
+            }
+            if i < N { U.set_row(i, &row) } else { V.set_row(i - N, &row) }
+        }
+    }
+    U
+}
+
+/// Blocks `(E11, E12, E22)` of the matrix exponential of the block upper triangular matrix
+/// `[X Y; 0 Z]`, using scaling and squaring of a truncated Taylor series. The block matrix is
+/// never formed, as its dimension `2N` cannot be expressed for generic const dimensions.
+pub(crate) fn block_triangular_exponential<const N: usize, F: RealField + Copy>(
+    X: &SMatrix<F, N, N>,
+    Y: &SMatrix<F, N, N>,
+    Z: &SMatrix<F, N, N>,
+) -> (SMatrix<F, N, N>, SMatrix<F, N, N>, SMatrix<F, N, N>) {
+    // Scale the matrix down to a norm of at most one half
+    let half: F = nalgebra::convert(0.5);
+    let norm = X.norm() + Y.norm() + Z.norm();
+    let mut squarings = 0;
+    let mut scale = F::one();
+    while norm * scale > half && squarings < 64 {
+        scale *= half;
+        squarings += 1;
+    }
+    let (X, Y, Z) = (X * scale, Y * scale, Z * scale);
+
+    // Taylor series, with term `k` being `[X Y; 0 Z]^k / k!`
+    let (mut E11, mut E12, mut E22) = (SMatrix::identity(), SMatrix::zeros(), SMatrix::identity());
+    let (mut T11, mut T12, mut T22) = (SMatrix::<F, N, N>::identity(), SMatrix::<F, N, N>::zeros(), SMatrix::<F, N, N>::identity());
+    for k in 1..=30 {
+        let k: F = nalgebra::convert(k as f64);
+        T12 = (T11 * Y + T12 * Z) / k;
+        T11 = T11 * X / k;
+        T22 = T22 * Z / k;
+        E11 += T11;
+        E12 += T12;
+        E22 += T22;
+        if T11.norm() + T12.norm() + T22.norm() <= F::default_epsilon() {
+            break;
+        }
+    }
+
+    // Undo the scaling by repeated squaring
+    for _ in 0..squarings {
+        E12 = E11 * E12 + E12 * E22;
+        E11 = E11 * E11;
+        E22 = E22 * E22;
+    }
+    (E11, E12, E22)
+}
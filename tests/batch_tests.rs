@@ -0,0 +1,211 @@
+#![allow(non_snake_case)]
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use assert_approx_eq::assert_approx_eq;
+    use kalman_filter::kalman::{KalmanError, KalmanFilter};
+    use kalman_filter::kalman_dynamic::DynKalmanFilter;
+    use kalman_filter::kalman_batch::BatchEstimator;
+    use kalman_filter::kalman_smoother::{FixedLagSmoother, KalmanSmoother};
+    use nalgebra::{matrix, DMatrix, DVector, SMatrix};
+    use rand::random;
+
+    #[test]
+    fn gravity_fall_map_matches_filter() {
+        let hz = 100;
+        let td: f64 = 1. / (hz as f64);
+        let A = matrix![
+            1., td ;
+            0., 1. ];
+        let B = matrix![
+            1.,0.;
+            0.,1.];
+        let Q = matrix![
+            1.,0.;
+            0.,1.];
+        let x_init = matrix![0.;0.];
+        let P_init = matrix![
+            1.,0.;
+            0.,1.];
+
+        let mut filter = KalmanFilter::new(A, Some(B), Q, x_init, P_init);
+        let batch = BatchEstimator::new(A, Some(B), Q, x_init, P_init);
+
+        const G: f64 = 9.82;
+        let mut inputs = Vec::new();
+        let mut measurements = Vec::new();
+        for i in 0..hz {
+            let measurement = (i % 10 == 0).then(|| {
+                let s = i as f64 / hz as f64;
+                matrix![G * 0.5 * s.powf(2.0) + (random::<f64>() - 0.5)]
+            });
+
+            if let Some(y) = measurement {
+                filter.update(&matrix![1.,0.], &matrix![1.], &y).unwrap();
+            }
+
+            let u: SMatrix<f64, 2, 1> = matrix![ 0.5*td.powf(2.0)*G ; td*G ];
+            filter.predict_with_input(u);
+
+            inputs.push(u);
+            measurements.push(measurement);
+        }
+
+        let solution = batch.solve(&matrix![1.,0.], &matrix![1.], &inputs, &measurements).unwrap();
+        assert_eq!(solution.states.len(), hz + 1);
+        assert_eq!(solution.covariance.nrows(), 2 * (hz + 1));
+
+        // The final state of the MAP trajectory has seen the same data as the filter
+        let last = solution.states.last().unwrap();
+        assert_approx_eq!(last[0], filter.get_state()[0], 1e-6);
+        assert_approx_eq!(last[1], filter.get_state()[1], 1e-6);
+    }
+
+    #[test]
+    fn gravity_fall_smoother_beats_filter() {
+        let hz = 100;
+        let td: f64 = 1. / (hz as f64);
+        let A = matrix![
+            1., td ;
+            0., 1. ];
+        let Q = matrix![
+            1e-6,0.;
+            0.,1e-4];
+
+        let mut filter = KalmanFilter::new(A, Some(SMatrix::identity()), Q, matrix![0.;0.], SMatrix::identity());
+        let mut smoother = KalmanSmoother::new();
+
+        const G: f64 = 9.82;
+        let mut truth = Vec::new();
+        let mut filtered = Vec::new();
+        for i in 0..hz {
+            let s = i as f64 / hz as f64;
+            filter.update(&matrix![1.,0.], &matrix![0.1], &matrix![G * 0.5 * s.powf(2.0) + (random::<f64>() - 0.5)]).unwrap();
+            smoother.record_step(&filter);
+
+            truth.push(G * 0.5 * s.powf(2.0));
+            filtered.push(filter.get_state()[0]);
+            filter.predict_with_input(matrix![ 0.5*td.powf(2.0)*G ; td*G ]);
+        }
+
+        let smoothed = smoother.smooth().unwrap();
+        assert_eq!(smoothed.len(), hz);
+        assert_eq!(smoothed[hz - 1].0[0], filtered[hz - 1]);
+
+        let rmse = |estimates: &mut dyn Iterator<Item = f64>| {
+            (estimates.zip(&truth).map(|(e, t)| (e - t).powi(2)).sum::<f64>() / hz as f64).sqrt()
+        };
+        let filtered_rmse = rmse(&mut filtered.iter().copied());
+        let smoothed_rmse = rmse(&mut smoothed.iter().map(|(x, _)| x[0]));
+        assert!(smoothed_rmse < filtered_rmse);
+    }
+
+    #[test]
+    fn dynamic_state_grows_with_landmarks() {
+        // Static landmark positions on a line, observed relative to each other
+        let landmarks = [1., 4., -2.];
+        let mut filter = DynKalmanFilter::<f64>::new(
+            DMatrix::identity(1, 1),
+            None,
+            DMatrix::zeros(1, 1),
+            DVector::from_element(1, landmarks[0]),
+            DMatrix::from_element(1, 1, 1e-4),
+        ).unwrap();
+
+        for n in 1..landmarks.len() {
+            // Extend the estimate with a new, uninformed landmark
+            let mut x = filter.get_state().clone().insert_row(n, 0.);
+            let mut P = filter.get_covariance().clone().insert_row(n, 0.).insert_column(n, 0.);
+            P[(n, n)] = 1e6;
+            assert_eq!(
+                DynKalmanFilter::new(DMatrix::identity(n + 1, n + 1), None, DMatrix::zeros(n, n), x.clone(), P.clone()).err(),
+                Some(KalmanError::DimensionMismatch),
+            );
+            filter = DynKalmanFilter::new(DMatrix::identity(n + 1, n + 1), None, DMatrix::zeros(n + 1, n + 1), x, P).unwrap();
+            assert_eq!(filter.dimension(), n + 1);
+
+            // Relative measurement between the new landmark and the first
+            let mut C = DMatrix::zeros(1, n + 1);
+            C[(0, 0)] = -1.;
+            C[(0, n)] = 1.;
+            let y = DVector::from_element(1, landmarks[n] - landmarks[0]);
+            let R = DMatrix::from_element(1, 1, 1e-4);
+            filter.predict();
+            filter.update(&C, &R, &y).unwrap();
+
+            // Measurements sized for the old dimension are rejected
+            assert_eq!(
+                filter.update(&DMatrix::zeros(1, n), &R, &y),
+                Err(KalmanError::DimensionMismatch),
+            );
+
+            x = filter.get_state().clone();
+            for (i, landmark) in landmarks.iter().take(n + 1).enumerate() {
+                assert_approx_eq!(x[i], *landmark, 0.05);
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_lag_matches_full_smoother() {
+        const L: usize = 30;
+        let td: f64 = 0.1;
+        let A = matrix![1., td; 0., 1.];
+        let Q = matrix![1e-3, 0.; 0., 1e-1];
+        let mut filter = KalmanFilter::<2, 1, f64>::new(A, None, Q, matrix![0.; 0.], SMatrix::identity());
+        let mut smoother = KalmanSmoother::new();
+        let mut fixed_lag = FixedLagSmoother::<2, L, f64>::new();
+
+        let mut lagged = Vec::new();
+        for i in 0..200 {
+            let s = i as f64 * td;
+            filter.update(&matrix![1., 0.], &matrix![0.1], &matrix![s.sin() + (random::<f64>() - 0.5)]).unwrap();
+            smoother.record_step(&filter);
+            let (x, _) = fixed_lag.record_step(&filter).unwrap();
+
+            // Filtered estimates until the buffer is full
+            if i < L {
+                assert_eq!(x, filter.get_state());
+            } else {
+                lagged.push(x);
+            }
+            filter.predict();
+        }
+
+        // The measurements beyond the lag carry little information about the lagged step
+        let smoothed = smoother.smooth().unwrap();
+        for (k, x) in lagged.iter().enumerate() {
+            assert!((x - smoothed[k].0).norm() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn smoother_with_variable_time_step() {
+        // Constant velocity without process noise, predicted over irregular steps
+        let mut filter = KalmanFilter::<2, 1, f64>::new(SMatrix::identity(), None, SMatrix::zeros(), matrix![0.; 0.], SMatrix::identity());
+        filter.set_continuous_model(matrix![0., 1.; 0., 0.], None, SMatrix::zeros());
+        let mut smoother = KalmanSmoother::new();
+
+        let steps = [0.1, 0.5, 0.05, 0.3, 0.2, 0.4];
+        let mut t = 0.;
+        for dt in steps {
+            filter.update(&matrix![1., 0.], &matrix![0.1], &matrix![1. + 2. * t + (random::<f64>() - 0.5)]).unwrap();
+            smoother.record_step(&filter);
+            filter.predict_dt(dt, matrix![0.]).unwrap();
+            t += dt;
+        }
+        filter.update(&matrix![1., 0.], &matrix![0.1], &matrix![1. + 2. * t]).unwrap();
+        smoother.record_step(&filter);
+
+        // Without process noise the smoothed trajectory follows the model of each step exactly
+        let smoothed = smoother.smooth().unwrap();
+        for (k, dt) in steps.iter().enumerate() {
+            let predicted = matrix![1., *dt; 0., 1.] * smoothed[k].0;
+            assert!((smoothed[k + 1].0 - predicted).norm() < 1e-9);
+        }
+    }
+}
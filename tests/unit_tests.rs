@@ -1,10 +1,28 @@
+#![allow(non_snake_case)]
+#![allow(clippy::unnecessary_cast)]
+
 #[cfg(test)]
 mod tests {
     extern crate std;
 
     use assert_approx_eq::assert_approx_eq;
-    use kalman_filter::kalman::KalmanFilter;
-    use nalgebra::matrix;
+    use kalman_filter::kalman::{self, CovarianceStep, FilterStep, KalmanError, KalmanFilter, KalmanFilterBuilder, TraceEvent};
+    use kalman_filter::kalman_bias;
+    use kalman_filter::kalman_borrowed;
+    use kalman_filter::kalman_classic;
+    use kalman_filter::kalman_dual_rate::DualRateFilter;
+    use kalman_filter::kalman_extended;
+    use kalman_filter::kalman_imm::ImmEstimator;
+    use kalman_filter::kalman_information;
+    use kalman_filter::kalman_fixed_gain::FixedGainEstimator;
+    use kalman_filter::kalman_kinematic::{ConstantAcceleration, ConstantVelocity};
+    use kalman_filter::kalman_nonlinear;
+    use kalman_filter::kalman_unscented;
+    use kalman_filter::kalman_monitor::{InformationGainWindow, SensitivityWindow, SensorHealth};
+    use kalman_filter::kalman_scale;
+    use kalman_filter::kalman_sensor::SensorFilter;
+    use kalman_filter::kalman_sqrt;
+    use nalgebra::{matrix, SMatrix};
     use rand::random;
 
     #[test]
@@ -144,7 +162,7 @@ mod tests {
                     &matrix![1.,0.], 
                     &matrix![1.],
                     &matrix![noise_p]
-                );
+                ).unwrap();
             }
     
             // Simulate with an external input as the gravitational acceleration
@@ -199,7 +217,7 @@ mod tests {
                     &matrix![1.,0.], 
                     &matrix![1.],
                     &matrix![pos_noise]
-                );
+                ).unwrap();
             }
 
             // Relatively faster velocity measurement
@@ -213,7 +231,7 @@ mod tests {
                     &matrix![0.,1.], 
                     &matrix![1.],
                     &matrix![vel_noise]
-                );
+                ).unwrap();
             }
 
             // Simulate with an external input as the gravitational acceleration
@@ -231,4 +249,2720 @@ mod tests {
         
     }
 
+    #[test]
+    fn drifting_bias_estimate() {
+        // Stationary 1D position with an unbiased reference sensor and a biased sensor
+        let mut filter: kalman_bias::KalmanFilter<1, 1, 1, 2, f32> = kalman_bias::KalmanFilter::new(
+            matrix![1.],
+            None,
+            matrix![1e-6],
+            matrix![1e-4],
+            matrix![0.],
+            matrix![1.],
+            matrix![1.],
+        );
+
+        let true_pos: f32 = 2.0;
+        let mut true_bias: f32 = 0.5;
+        for i in 0..2000 {
+            // Slowly drifting bias
+            true_bias += 5e-4;
+
+            if i % 10 == 0 {
+                let ref_noise = true_pos + (random::<f32>() - 0.5);
+                filter.update(&matrix![1.], &matrix![0.], &matrix![1. / 12.], &matrix![ref_noise]).unwrap();
+            }
+
+            let biased = true_pos + true_bias + (random::<f32>() - 0.5);
+            filter.update(&matrix![1.], &matrix![1.], &matrix![1. / 12.], &matrix![biased]).unwrap();
+
+            filter.predict();
+        }
+
+        assert_approx_eq!(filter.get_state()[0], true_pos, 0.2);
+        assert_approx_eq!(filter.get_bias_estimate()[0], true_bias, 0.2);
+    }
+
+    // Pendulum dynamics with state [angle, angular velocity]
+    fn pendulum_f(x: SMatrix<f64, 2, 1>, _u: SMatrix<f64, 1, 1>) -> SMatrix<f64, 2, 1> {
+        matrix![x[1]; -9.82 * x[0].sin()]
+    }
+
+    fn pendulum_fj(x: SMatrix<f64, 2, 1>, _u: SMatrix<f64, 1, 1>) -> SMatrix<f64, 2, 2> {
+        matrix![
+            0., 1.;
+            -9.82 * x[0].cos(), 0.]
+    }
+
+    // Coarse Euler discretization of the pendulum at 0.1 seconds
+    fn pendulum_euler_f(x: SMatrix<f64, 2, 1>, u: SMatrix<f64, 1, 1>) -> SMatrix<f64, 2, 1> {
+        x + pendulum_f(x, u) * 0.1
+    }
+
+    fn pendulum_euler_fj(x: SMatrix<f64, 2, 1>, u: SMatrix<f64, 1, 1>) -> SMatrix<f64, 2, 2> {
+        SMatrix::identity() + pendulum_fj(x, u) * 0.1
+    }
+
+    fn angle_h(x: SMatrix<f64, 2, 1>, _u: SMatrix<f64, 1, 1>) -> SMatrix<f64, 1, 1> {
+        matrix![x[0]]
+    }
+
+    fn angle_hj(_x: SMatrix<f64, 2, 1>, _u: SMatrix<f64, 1, 1>) -> SMatrix<f64, 1, 2> {
+        matrix![1., 0.]
+    }
+
+    fn pendulum_filter(ff: fn(SMatrix<f64, 2, 1>, SMatrix<f64, 1, 1>) -> SMatrix<f64, 2, 1>,
+                       fj: fn(SMatrix<f64, 2, 1>, SMatrix<f64, 1, 1>) -> SMatrix<f64, 2, 2>,
+                       q: SMatrix<f64, 2, 2>) -> kalman_extended::KalmanFilter<2, 1, 1, f64> {
+        let mut filter = kalman_extended::KalmanFilter::new(
+            ff, fj, angle_h, angle_hj,
+            q,
+            matrix![0.01],
+            matrix![1.0; 0.],
+            matrix![
+                0.01, 0.;
+                0., 0.01],
+        );
+        filter.set_continuous_model(pendulum_f, pendulum_fj, matrix![0., 0.; 0., 0.1]);
+        filter
+    }
+
+    #[test]
+    fn continuous_discrete_riccati_predict() {
+        // Finely integrated reference
+        let mut reference = pendulum_filter(pendulum_euler_f, pendulum_euler_fj, SMatrix::zeros());
+        reference.set_substeps(1000);
+
+        // Riccati integration with few substeps
+        let mut riccati = pendulum_filter(pendulum_euler_f, pendulum_euler_fj, SMatrix::zeros());
+        riccati.set_substeps(4);
+
+        // Coarse discretized approximation
+        let mut coarse = pendulum_filter(pendulum_euler_f, pendulum_euler_fj, matrix![0., 0.; 0., 0.1 * 0.1]);
+
+        for _ in 0..10 {
+            reference.predict_continuous(0.1).unwrap();
+            riccati.predict_continuous(0.1).unwrap();
+            coarse.predict();
+        }
+
+        let reference_cov = reference.get_covariance();
+        let riccati_err = (riccati.get_covariance() - reference_cov).norm();
+        let coarse_err = (coarse.get_covariance() - reference_cov).norm();
+        assert!(riccati_err < 1e-3 * reference_cov.norm());
+        assert!(riccati_err < 0.1 * coarse_err);
+
+        let state_err = (riccati.get_state() - reference.get_state()).norm();
+        assert!(state_err < 1e-3);
+
+        // Without a continuous model nothing is integrated
+        let mut discrete = kalman_extended::KalmanFilter::new(
+            pendulum_euler_f, pendulum_euler_fj, angle_h, angle_hj,
+            SMatrix::zeros(),
+            matrix![0.01],
+            matrix![1.0; 0.],
+            matrix![0.01, 0.; 0., 0.01],
+        );
+        assert_eq!(discrete.predict_continuous(0.1), Err(KalmanError::NoContinuousModel));
+        assert_eq!(discrete.get_state(), matrix![1.0; 0.]);
+    }
+
+    // Gravity fall model used by the tests, with the same initial values as above
+    fn gravity_filter(td: f32) -> KalmanFilter<2, 2, f32> {
+        KalmanFilter::new(
+            matrix![
+                1., td ;
+                0., 1. ],
+            Some(matrix![
+                1.,0.;
+                0.,1.]),
+            matrix![
+                1.,0.;
+                0.,1.],
+            matrix![
+                0.;0.],
+            matrix![
+                1.,0.;
+                0.,1.],
+        )
+    }
+
+    #[test]
+    fn window_information_gain() {
+        let mut filter = gravity_filter(0.01);
+        let mut window: InformationGainWindow<10, f32> = InformationGainWindow::new();
+
+        // Pure prediction gains no information
+        for _ in 0..20 {
+            filter.predict();
+            window.record(&filter);
+        }
+        assert!(window.window_information_gain() < 1e-6);
+
+        // A burst of precise position and velocity measurements
+        for _ in 0..10 {
+            filter.update(&matrix![1.,0.], &matrix![0.01], &matrix![0.]).unwrap();
+            filter.update(&matrix![0.,1.], &matrix![0.01], &matrix![0.]).unwrap();
+            window.record(&filter);
+            filter.predict();
+        }
+        assert!(window.window_information_gain() > 5.);
+
+        // Back to pure prediction, the burst leaves the window
+        for _ in 0..10 {
+            filter.predict();
+            window.record(&filter);
+        }
+        assert!(window.window_information_gain() < 1e-6);
+    }
+
+    #[test]
+    fn prewhitened_update() {
+        let C = matrix![
+            1., 0.;
+            0., 1.;
+            1., 1.];
+        let R = matrix![
+            1.0, 0.5, 0.2;
+            0.5, 2.0, 0.3;
+            0.2, 0.3, 1.5];
+        let y = matrix![1.0; 2.0; 2.5];
+
+        let mut dense = gravity_filter(0.01);
+        dense.update(&C, &R, &y).unwrap();
+        dense.predict();
+
+        let (C_w, y_w) = kalman::prewhiten(&C, &R, &y).unwrap();
+        let mut whitened = gravity_filter(0.01);
+        whitened.update(&C_w, &SMatrix::identity(), &y_w).unwrap();
+        whitened.predict();
+
+        assert_approx_eq!(dense.get_state()[0], whitened.get_state()[0], 1e-5);
+        assert_approx_eq!(dense.get_state()[1], whitened.get_state()[1], 1e-5);
+    }
+
+    #[test]
+    fn state_with_time() {
+        let td: f32 = 0.01;
+        let mut filter = gravity_filter(td);
+        filter.set_time_step(td);
+
+        const G: f32 = 9.82;
+        for _ in 0..250 {
+            filter.predict_with_input(matrix![ 0.5*td.powf(2.0)*G ; td*G ]);
+        }
+
+        let (time, state) = filter.get_state_with_time();
+        assert_approx_eq!(time, 2.5, 1e-3);
+        assert_approx_eq!(state[0], G * 0.5 * time.powf(2.0), 1e-2);
+    }
+
+    #[test]
+    fn measurement_influence() {
+        let C = matrix![
+            1., 0.;
+            0., 1.];
+
+        let mut filter = gravity_filter(0.01);
+        filter.update(&C, &(SMatrix::identity() * 1e-6), &matrix![1.;1.]).unwrap();
+        assert!(filter.measurement_influence() > 0.99);
+
+        let mut filter = gravity_filter(0.01);
+        filter.update(&C, &(SMatrix::identity() * 1e6), &matrix![1.;1.]).unwrap();
+        assert!(filter.measurement_influence() < 0.01);
+    }
+
+    // Track a 1D target that makes a sudden maneuver, returning the tracking error
+    // during the maneuver and the trajectory of the inflation of Q
+    fn maneuver_tracking(filter: &mut KalmanFilter<2, 1, f32>) -> (f32, [f32; 200]) {
+        let mut inflation = [0.; 200];
+        let mut error = 0.;
+        let (mut pos, mut vel): (f32, f32) = (0., 1.);
+        for (i, q) in inflation.iter_mut().enumerate() {
+            // Sudden velocity change
+            if i == 100 { vel = 5. }
+            pos += vel * 0.1;
+
+            filter.update(&matrix![1., 0.], &matrix![0.01], &matrix![pos + 0.1 * (random::<f32>() - 0.5)]).unwrap();
+            filter.predict();
+            *q = filter.get_q_inflation();
+
+            if (100..150).contains(&i) {
+                error += (filter.get_state()[1] - vel).abs();
+            }
+        }
+        (error, inflation)
+    }
+
+    #[test]
+    fn innovation_q_inflation() {
+        let cv_filter = || KalmanFilter::new(
+            matrix![
+                1., 0.1 ;
+                0., 1. ],
+            None,
+            matrix![
+                1e-4, 0.;
+                0., 1e-4 ],
+            matrix![
+                0.;1.],
+            matrix![
+                1.,0.;
+                0.,1.],
+        );
+
+        let mut nominal = cv_filter();
+        let (nominal_error, _) = maneuver_tracking(&mut nominal);
+
+        let mut inflated = cv_filter();
+        inflated.set_q_inflation(9., 0.8);
+        let (inflated_error, inflation) = maneuver_tracking(&mut inflated);
+
+        // Inflates on the maneuver and returns to nominal afterwards
+        assert!(inflation[100..110].iter().any(|q| *q > 2.));
+        assert!(inflation[199] < 1.01);
+        assert!(inflated_error < nominal_error);
+    }
+
+    #[test]
+    fn linear_combination_variance() {
+        let td: f32 = 0.1;
+        let mut filter = gravity_filter(td);
+        filter.predict();
+
+        // P = A*A^T + Q for the identity initial covariance and Q
+        let P = matrix![
+            2. + td * td, td;
+            td, 2.];
+        let a = matrix![1.; 1.];
+        let expected = P[(0, 0)] + 2. * P[(0, 1)] + P[(1, 1)];
+
+        assert_approx_eq!(filter.linear_combination_variance(&a), expected, 1e-5);
+        assert_approx_eq!(filter.linear_combination_variance(&a), (a.transpose() * P * a)[0], 1e-5);
+    }
+
+    #[test]
+    fn cross_covariance_block() {
+        // Gravity model augmented with a position sensor bias
+        let td: f32 = 0.01;
+        let mut filter: kalman_bias::KalmanFilter<2, 2, 1, 3, f32> = kalman_bias::KalmanFilter::new(
+            matrix![
+                1., td ;
+                0., 1. ],
+            Some(matrix![
+                1.,0.;
+                0.,1.]),
+            matrix![
+                1.,0.;
+                0.,1.],
+            matrix![1e-4],
+            matrix![
+                0.;0.],
+            matrix![
+                1.,0.;
+                0.,1.],
+            matrix![1.],
+        );
+        for _ in 0..5 {
+            filter.update(&matrix![1., 0.], &matrix![1.], &matrix![0.1], &matrix![1.]).unwrap();
+            filter.update(&matrix![1., 0.], &matrix![0.], &matrix![0.1], &matrix![0.5]).unwrap();
+            filter.predict();
+        }
+        filter.update(&matrix![1., 0.], &matrix![1.], &matrix![0.1], &matrix![1.]).unwrap();
+
+        // Covariances recovered through `Cov(a,b) = (Var(a+b) - Var(a) - Var(b)) / 2`
+        let augmented = filter.augmented();
+        let var = |a: [f32; 3]| augmented.linear_combination_variance(&matrix![a[0]; a[1]; a[2]]);
+        let cov = |i: usize, j: usize| {
+            let mut a = [0.; 3];
+            let mut b = [0.; 3];
+            let mut ab = [0.; 3];
+            a[i] = 1.;
+            b[j] = 1.;
+            ab[i] += 1.;
+            ab[j] += 1.;
+            (var(ab) - var(a) - var(b)) / 2.
+        };
+
+        let block = augmented.cross_covariance(&[0, 1], &[2]);
+        assert_approx_eq!(block[(0, 0)], cov(0, 2), 1e-4);
+        assert_approx_eq!(block[(1, 0)], cov(1, 2), 1e-4);
+        assert!(block[(0, 0)].abs() > 1e-3);
+    }
+
+    #[test]
+    fn settle_to_steady_state() {
+        let td: f64 = 0.1;
+        let A = matrix![
+            1., td ;
+            0., 1. ];
+        let Q = matrix![
+            0.01, 0.;
+            0., 0.01];
+        let C = matrix![1., 0.];
+        let R = matrix![0.5];
+
+        let mut filter: KalmanFilter<2, 1, f64> = KalmanFilter::new(
+            A, None, Q,
+            matrix![0.; 0.],
+            matrix![
+                100., 0.;
+                0., 100.],
+        );
+        let (_, P) = filter.settle(&C, &R, &matrix![0.], 1e-10, 10_000).unwrap();
+
+        // Steady state posterior covariance from the Riccati recursion
+        let mut P_post: SMatrix<f64, 2, 2> = SMatrix::identity();
+        for _ in 0..10_000 {
+            let P_prio = A * P_post * A.transpose() + Q;
+            let S = C * P_prio * C.transpose() + R;
+            P_post = P_prio - P_prio * C.transpose() * S.try_inverse().unwrap() * C * P_prio;
+        }
+
+        assert!((P - P_post).norm() < 1e-6);
+    }
+
+    #[test]
+    fn validate_measurement_noise() {
+        let filter = gravity_filter(0.01);
+        let C = matrix![
+            1., 0.;
+            0., 1.];
+
+        // Indefinite R
+        let R = matrix![
+            1., 2.;
+            2., 1.];
+        assert_eq!(filter.validate_measurement(&C, &R, 1e6), Err(KalmanError::NonPositiveDefiniteNoise));
+
+        // Non-symmetric R
+        let R = matrix![
+            1., 0.5;
+            0., 1.];
+        assert_eq!(filter.validate_measurement(&C, &R, 1e6), Err(KalmanError::NonPositiveDefiniteNoise));
+
+        // Redundant measurement of the same state with tiny noise
+        let C = matrix![
+            1., 0.;
+            1., 0.];
+        let R = SMatrix::identity() * 1e-9;
+        assert_eq!(filter.validate_measurement(&C, &R, 1e6), Err(KalmanError::IllConditionedInnovation));
+        assert_eq!(filter.validate_measurement(&C, &SMatrix::identity(), 1e6), Ok(()));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn debug_validation_rejects_indefinite_noise() {
+        let mut filter = gravity_filter(0.01);
+        filter.set_debug_validation(Some(1e6));
+        let result = filter.update(&matrix![1., 0.], &matrix![-1.], &matrix![0.]);
+        assert_eq!(result, Err(KalmanError::NonPositiveDefiniteNoise));
+    }
+
+    #[test]
+    fn gate_widens_after_prediction_gap() {
+        let cv_filter = || KalmanFilter::<2, 1, f32>::new(
+            matrix![
+                1., 0.1 ;
+                0., 1. ],
+            None,
+            matrix![
+                1e-3, 0.;
+                0., 1e-3 ],
+            matrix![
+                0.;1.],
+            matrix![
+                0.01,0.;
+                0.,0.01],
+        );
+
+        // 99% chi-squared quantile for one degree of freedom
+        let threshold = 6.63;
+
+        // The true velocity is slightly off, causing a growing position error
+        let true_pos = |i: usize| 1.05 * 0.1 * i as f32;
+
+        // Fresh and confident, a measurement this far off is rejected
+        let mut filter = cv_filter();
+        for _ in 0..10 { filter.predict() }
+        assert!(!filter.update_gated(&matrix![1., 0.], &matrix![0.001], &matrix![true_pos(10) + 1.0], threshold));
+
+        // After a long gap the same kind of measurement is the one needed to reacquire
+        let mut filter = cv_filter();
+        for _ in 0..500 { filter.predict() }
+        let predicted = filter.get_state()[0];
+        assert!((true_pos(500) - predicted).abs() > 2.);
+        assert!(filter.update_gated(&matrix![1., 0.], &matrix![0.001], &matrix![true_pos(500)], threshold));
+        assert_approx_eq!(filter.get_state()[0], true_pos(500), 0.1);
+    }
+
+    #[test]
+    fn composed_models_predict() {
+        let A1 = matrix![
+            1., 0.01 ;
+            0., 1. ];
+        let B1 = matrix![
+            0.5,0.;
+            0.,1.];
+        let A2 = matrix![
+            0.9, 0.1 ;
+            0., 0.95 ];
+        let B2 = matrix![
+            0.,0.2;
+            0.1,0.];
+        let u = matrix![1.; -2.];
+
+        // Move away from the zero initial state first
+        let mut sequential = gravity_filter(0.01);
+        sequential.predict_with_input(u);
+        sequential.set_A(A1);
+        sequential.set_B(Some(B1));
+        sequential.predict_with_input(u);
+        sequential.set_A(A2);
+        sequential.set_B(Some(B2));
+        sequential.predict_with_input(u);
+
+        let (A, B) = kalman::compose_models(&A1, &B1, &A2, &B2);
+        let mut composed = gravity_filter(0.01);
+        composed.predict_with_input(u);
+        composed.set_A(A);
+        composed.set_B(Some(B));
+        composed.predict_with_input(u);
+
+        assert_approx_eq!(sequential.get_state()[0], composed.get_state()[0], 1e-6);
+        assert_approx_eq!(sequential.get_state()[1], composed.get_state()[1], 1e-6);
+    }
+
+    #[test]
+    fn standardized_innovation() {
+        // Identity covariance with unit measurement noise gives `S = 2` for both components
+        let mut filter = gravity_filter(0.01);
+        let C = matrix![
+            1., 0.;
+            0., 1.];
+        let R = matrix![
+            1., 0.;
+            0., 1.];
+        assert_eq!(filter.standardized_innovation::<2>(), None);
+
+        filter.update(&C, &R, &matrix![3. * 2f32.sqrt(); -1.5 * 2f32.sqrt()]).unwrap();
+        let z = filter.standardized_innovation::<2>().unwrap();
+        assert_approx_eq!(z[0], 3., 1e-5);
+        assert_approx_eq!(z[1], -1.5, 1e-5);
+        assert_eq!(filter.standardized_innovation::<1>(), None);
+    }
+
+    #[test]
+    fn hypothetical_posterior() {
+        let mut filter = gravity_filter(0.01);
+        for _ in 0..10 { filter.predict() }
+
+        let C = matrix![1., 0.];
+        let R = matrix![0.5];
+        let y = matrix![2.];
+
+        let before = filter.get_state();
+        let (x, P) = filter.hypothetical_posterior(&C, &R, &y).unwrap();
+        assert_eq!(filter.get_state(), before);
+
+        filter.update(&C, &R, &y).unwrap();
+        let covariance = filter.cross_covariance(&[0, 1], &[0, 1]);
+        assert!((filter.get_state() - x).norm() < 1e-6);
+        assert!((covariance - P).norm() < 1e-6);
+
+        // State bounds and equality constraints are applied as in `.update`
+        let mut filter = gravity_filter(0.01);
+        for _ in 0..10 { filter.predict() }
+        filter.set_state_bounds(matrix![-1.; -10.], matrix![1.; 10.]);
+        filter.set_equality_constraint(&matrix![0., 1.], &matrix![-0.5]);
+        let (x, P) = filter.hypothetical_posterior(&C, &R, &y).unwrap();
+        filter.update(&C, &R, &y).unwrap();
+        assert_eq!(x[0], 1.);
+        assert_approx_eq!(x[1], -0.5, 1e-6);
+        assert!((filter.get_state() - x).norm() < 1e-6);
+        assert!((filter.get_covariance() - P).norm() < 1e-6);
+    }
+
+    #[test]
+    fn forecast_altitude_floor() {
+        let td = 0.1;
+        let mut filter = KalmanFilter::<2, 2, f32>::new(
+            matrix![1., td; 0., 1.],
+            None,
+            SMatrix::identity() * 0.01,
+            matrix![10.; -5.],
+            SMatrix::identity(),
+        );
+        let u = SMatrix::zeros();
+
+        // Unbounded linear extrapolation descends below the ground
+        let (x, _) = filter.predict_n(50, &u);
+        assert!(x[0] < 0.);
+
+        filter.set_forecast_bounds(matrix![0.; f32::NEG_INFINITY], matrix![f32::INFINITY; f32::INFINITY]);
+        assert!(filter.forecast_iter(&u).take(50).all(|(x, _)| x[0] >= 0.));
+        let (x, _) = filter.predict_n(50, &u);
+        assert_eq!(x[0], 0.);
+
+        // The filter itself is untouched
+        assert_eq!(filter.get_state(), matrix![10.; -5.]);
+    }
+
+    #[test]
+    fn calibrate_measurement_noise() {
+        // Correlated uniform noise, where each draw has variance 1/12
+        let measurements: std::vec::Vec<SMatrix<f64, 2, 1>> = (0..20000)
+            .map(|_| {
+                let (a, b) = (random::<f64>() - 0.5, random::<f64>() - 0.5);
+                matrix![3. + a; -1. + a + b]
+            })
+            .collect();
+
+        let R = kalman::calibrate_r(&measurements);
+        let v = 1. / 12.;
+        assert!((R - matrix![v, v; v, 2. * v]).norm() < 0.01);
+    }
+
+    #[test]
+    fn state_masked_update() {
+        let mut filter = gravity_filter(0.1);
+        for _ in 0..5 { filter.predict() }
+
+        // Correlated states, measuring velocity would normally move position too
+        let C = matrix![0., 1.];
+        let R = matrix![0.1];
+        let y = matrix![3.];
+
+        let mut unmasked = gravity_filter(0.1);
+        for _ in 0..5 { unmasked.predict() }
+        unmasked.update(&C, &R, &y).unwrap();
+        assert!(unmasked.get_state()[0] != 0.);
+
+        filter.update_with_state_mask(&C, &R, &y, &[false, true]).unwrap();
+        assert_eq!(filter.get_state()[0], 0.);
+        assert_approx_eq!(filter.get_state()[1], unmasked.get_state()[1], 1e-6);
+    }
+
+    #[test]
+    fn batch_log_likelihood() {
+        let td = 0.1;
+        let C = matrix![1., 0.];
+        let R = matrix![0.5];
+        let inputs: std::vec::Vec<SMatrix<f32, 2, 1>> = (0..20).map(|_| matrix![0.; -9.81 * td]).collect();
+        let measurements: std::vec::Vec<SMatrix<f32, 1, 1>> =
+            (0..20).map(|k| matrix![-4.9 * (k as f32 * td).powi(2) + 0.3 * (random::<f32>() - 0.5)]).collect();
+
+        let filter = gravity_filter(td);
+        let batch = filter.batch_log_likelihood(&C, &R, &inputs, &measurements).unwrap();
+
+        // Manual accumulation over individual updates
+        let mut manual_filter = gravity_filter(td);
+        let mut manual = 0.;
+        for (u, y) in inputs.iter().zip(&measurements) {
+            let S = manual_filter.linear_combination_variance(&C.transpose()) + R[0];
+            let y_res = y[0] - (C * manual_filter.get_state())[0];
+            manual += -0.5 * ((2. * core::f32::consts::PI * S).ln() + y_res * y_res / S);
+            manual_filter.update(&C, &R, y).unwrap();
+            manual_filter.predict_with_input(*u);
+        }
+        assert_approx_eq!(batch, manual, 1e-3);
+
+        // The filter itself is untouched
+        assert_eq!(filter.get_state(), matrix![0.; 0.]);
+        // Mismatched sequence lengths are rejected rather than truncated
+        assert!(filter.batch_log_likelihood(&C, &R, &inputs[..19], &measurements).is_none());
+        assert!(filter.batch_log_likelihood(&C, &R, &inputs, &measurements[..19]).is_none());
+    }
+
+    #[test]
+    fn correlation_matrix() {
+        let filter = KalmanFilter::<2, 2, f32>::new(
+            SMatrix::identity(),
+            None,
+            SMatrix::identity(),
+            SMatrix::zeros(),
+            matrix![4., 3.; 3., 9.],
+        );
+        let correlation = filter.get_correlation_matrix();
+        assert_approx_eq!(correlation[(0, 0)], 1., 1e-6);
+        assert_approx_eq!(correlation[(1, 1)], 1., 1e-6);
+        assert_approx_eq!(correlation[(0, 1)], 0.5, 1e-6);
+        assert_approx_eq!(correlation[(1, 0)], 0.5, 1e-6);
+    }
+
+    #[test]
+    fn smoothed_nis() {
+        // Constant scalar observed with uniform noise of variance 1/12
+        let smoothed = |R: f64| {
+            let mut filter = KalmanFilter::<1, 1, f64>::new(
+                matrix![1.],
+                None,
+                matrix![1e-6],
+                matrix![0.],
+                matrix![1.],
+            );
+            filter.set_nis_time_constant(200.);
+            for _ in 0..5000 {
+                filter.update(&matrix![1.], &matrix![R], &matrix![2. + random::<f64>() - 0.5]).unwrap();
+                filter.predict();
+            }
+            filter.smoothed_nis().unwrap()
+        };
+
+        assert!((smoothed(1. / 12.) - 1.).abs() < 0.3);
+        assert!(smoothed(0.1 / 12.) > 5.);
+    }
+
+    #[test]
+    fn kinematic_accessors() {
+        let mut cv = ConstantVelocity::<f32>::new(0.1, 0.01, matrix![1.; 2.], SMatrix::identity());
+        assert_eq!(cv.position(), 1.);
+        assert_eq!(cv.velocity(), 2.);
+
+        cv.predict();
+        assert_approx_eq!(cv.position(), 1.2, 1e-6);
+        assert_approx_eq!(cv.velocity(), 2., 1e-6);
+        assert_eq!(cv.position(), cv.filter().get_state()[0]);
+        assert_eq!(cv.velocity(), cv.filter().get_state()[1]);
+
+        let ca = ConstantAcceleration::<f32>::new(0.1, 0.01, matrix![1.; 2.; 3.], SMatrix::identity());
+        assert_eq!(ca.position(), 1.);
+        assert_eq!(ca.velocity(), 2.);
+        assert_eq!(ca.acceleration(), 3.);
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut filter = gravity_filter(0.1);
+        filter.predict();
+        filter.update(&matrix![1., 0.], &matrix![0.5], &matrix![0.3]).unwrap();
+
+        // A, B, Q and P are 2x2, x is 2x1
+        assert_eq!(KalmanFilter::<2, 2, f32>::encoded_len(), 18 * 4);
+        assert_eq!(KalmanFilter::<2, 2, f64>::encoded_len(), 18 * 8);
+
+        let mut bytes = [0u8; 72];
+        assert_eq!(filter.to_bytes(&mut bytes[..71]), None);
+        assert_eq!(filter.to_bytes(&mut bytes), Some(72));
+
+        let mut loaded = KalmanFilter::<2, 2, f32>::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.get_state(), filter.get_state());
+        assert_eq!(loaded.cross_covariance(&[0, 1], &[0, 1]), filter.cross_covariance(&[0, 1], &[0, 1]));
+
+        // The model is restored too
+        filter.predict();
+        loaded.predict();
+        assert_eq!(loaded.get_state(), filter.get_state());
+    }
+
+    #[test]
+    fn cast_precision() {
+        let td = 0.1;
+        let mut filter = KalmanFilter::<2, 2, f64>::new(
+            matrix![1., td; 0., 1.],
+            None,
+            SMatrix::identity(),
+            SMatrix::zeros(),
+            SMatrix::identity(),
+        );
+        for k in 0..10 {
+            filter.predict();
+            filter.update(&matrix![1., 0.], &matrix![0.5], &matrix![-0.1 * k as f64]).unwrap();
+        }
+
+        let mut single = KalmanFilter::<2, 2, f32>::from(&filter);
+        assert!((single.get_state() - filter.get_state().cast::<f32>()).norm() < 1e-6);
+
+        // Both continue identically to within f32 precision
+        filter.predict();
+        single.predict();
+        assert!((single.get_state() - filter.get_state().cast::<f32>()).norm() < 1e-5);
+        assert!((KalmanFilter::<2, 2, f64>::from(single).get_state() - filter.get_state()).norm() < 1e-5);
+    }
+
+    #[test]
+    fn steps_until_variance_exceeds() {
+        let mut filter = gravity_filter(0.1);
+        filter.update(&matrix![1., 0.], &matrix![0.1], &matrix![0.]).unwrap();
+        let u = matrix![0.; -0.981];
+
+        let steps = filter.steps_until_variance_exceeds(0, 50., &u, 1000).unwrap();
+
+        // Manually iterate predictions on a clone
+        let mut clone = filter.clone();
+        let mut manual = 0;
+        while clone.linear_combination_variance(&matrix![1.; 0.]) <= 50. {
+            clone.predict_with_input(u);
+            manual += 1;
+        }
+        assert_eq!(steps, manual);
+        assert_eq!(filter.steps_until_variance_exceeds(0, 50., &u, steps - 1), None);
+    }
+
+    #[test]
+    fn scale_factor_estimate() {
+        let mut filter = kalman_scale::KalmanFilter::<1, 1, 2, f64>::new(
+            matrix![1.],
+            None,
+            matrix![1e-6],
+            1e-8,
+            matrix![0.],
+            matrix![100.],
+            1.,
+        );
+
+        // Alternate between a reference sensor and a sensor with a gain of 1.1
+        let C = matrix![1.];
+        let R = matrix![0.01];
+        for k in 0..2000 {
+            let noise = 0.2 * (random::<f64>() - 0.5);
+            if k % 2 == 0 {
+                filter.update_unscaled(&C, &R, &matrix![5. + noise]).unwrap();
+            } else {
+                filter.update(&C, &R, &matrix![1.1 * 5. + noise]).unwrap();
+            }
+            filter.predict();
+        }
+
+        assert_approx_eq!(filter.get_state()[0], 5., 0.02);
+        assert_approx_eq!(filter.get_scale_estimate(), 1.1, 0.005);
+    }
+
+    #[test]
+    fn predict_backward() {
+        let td = 0.1;
+        let mut filter = gravity_filter(td);
+        filter.update(&matrix![1., 0.], &matrix![0.1], &matrix![2.]).unwrap();
+        let original = filter.get_state();
+        let variance = filter.linear_combination_variance(&matrix![1.; 0.]);
+
+        let u = matrix![0.; -9.81 * td];
+        for _ in 0..10 { filter.predict_with_input(u) }
+        for _ in 0..10 { filter.predict_backward(u).unwrap() }
+
+        assert!((filter.get_state() - original).norm() < 1e-4);
+        assert_approx_eq!(filter.get_time(), 0., 1e-5);
+        assert!(filter.linear_combination_variance(&matrix![1.; 0.]) > variance);
+
+        // Same forgetting factor and process noise as the forward prediction
+        let mut scalar = KalmanFilter::<1, 1, f64>::new(matrix![2.], None, matrix![0.5], matrix![0.], matrix![1.]);
+        scalar.set_forgetting_factor(1.5);
+        scalar.predict_backward(matrix![0.]).unwrap();
+        assert_approx_eq!(scalar.get_covariance()[0], (1.5 + 0.5) / 4., 1e-12);
+
+        // Accumulated measurements are fused before stepping back
+        let mut accumulated = gravity_filter(td);
+        let mut updated = gravity_filter(td);
+        accumulated.accumulate(&matrix![1., 0.], &matrix![0.1], &matrix![2.]).unwrap();
+        updated.update(&matrix![1., 0.], &matrix![0.1], &matrix![2.]).unwrap();
+        accumulated.predict_backward(u).unwrap();
+        updated.predict_backward(u).unwrap();
+        assert!((accumulated.get_state() - updated.get_state()).norm() < 1e-5);
+        assert!((accumulated.get_covariance() - updated.get_covariance()).norm() < 1e-5);
+
+        // Singular dynamics cannot be inverted
+        filter.set_A(SMatrix::zeros());
+        assert_eq!(filter.predict_backward(u), Err(KalmanError::SingularTransition));
+    }
+
+    #[test]
+    fn fuse_partial_fix() {
+        let mut filter = gravity_filter(0.1);
+        for _ in 0..10 { filter.predict() }
+
+        filter.fuse_fix(&matrix![5.; 100.], &matrix![1e-4, 0.; 0., 1.], &[true, false]).unwrap();
+
+        // Position snaps to the fix, velocity is corrected through correlation only
+        let x = filter.get_state();
+        assert_approx_eq!(x[0], 5., 1e-3);
+        assert!(x[1] > 0. && x[1] < 5.);
+    }
+
+    #[test]
+    fn information_form_round_trip() {
+        let mut filter = gravity_filter(0.1);
+        for _ in 0..5 { filter.predict() }
+        filter.update(&matrix![1., 0.], &matrix![0.5], &matrix![1.]).unwrap();
+        let x = filter.get_state();
+        let P = filter.cross_covariance(&[0, 1], &[0, 1]);
+
+        let (information_vector, information_matrix) = filter.to_information_form().unwrap();
+
+        let mut other = gravity_filter(0.1);
+        assert!(other.set_information_form(&information_vector, &information_matrix));
+        assert!((other.get_state() - x).norm() < 1e-4);
+        assert!((other.cross_covariance(&[0, 1], &[0, 1]) - P).norm() < 1e-4);
+
+        assert!(!other.set_information_form(&information_vector, &SMatrix::zeros()));
+    }
+
+    #[test]
+    fn measurement_inflation() {
+        let correction = |factor: f32| {
+            let mut filter = gravity_filter(0.1);
+            filter.set_measurement_inflation(factor);
+            filter.update(&matrix![1., 0.], &matrix![0.5], &matrix![1.]).unwrap();
+            filter.get_state()[0]
+        };
+
+        assert!(correction(4.) < correction(1.));
+        assert_approx_eq!(correction(4.), 1. / 3., 1e-6);
+    }
+
+    #[test]
+    fn open_loop_detection() {
+        let mut filter = gravity_filter(0.1);
+        let C = matrix![1., 0.];
+        let R = matrix![0.1];
+
+        filter.update(&C, &R, &matrix![0.]).unwrap();
+        filter.predict();
+        assert_eq!(filter.consecutive_no_update(), 0);
+
+        // Outliers are rejected by the gate
+        for k in 1..=5 {
+            assert!(!filter.update_gated(&C, &R, &matrix![1000.], 9.));
+            filter.predict();
+            assert_eq!(filter.consecutive_no_update(), k);
+            assert_eq!(filter.is_open_loop(5), k >= 5);
+        }
+
+        filter.update(&C, &R, &matrix![0.]).unwrap();
+        assert_eq!(filter.consecutive_no_update(), 0);
+        assert!(!filter.is_open_loop(5));
+    }
+
+    #[test]
+    fn run_input_schedule() {
+        // Same setup as the gravity fall at 10 Hz
+        const G: f32 = 9.82;
+        let td: f32 = 0.1;
+        let inputs = [matrix![0.5 * td.powf(2.0) * G; td * G]; 50];
+
+        let state = gravity_filter(td).run_inputs(&inputs);
+        assert_approx_eq!(state[0], G * 0.5 * 25., 1e-3);
+        assert_approx_eq!(state[1], G * 5., 1e-3);
+
+        let states = gravity_filter(td).run_inputs_collect(&inputs);
+        assert_eq!(states.len(), 50);
+        assert_eq!(states[49], state);
+        assert_approx_eq!(states[9][1], G, 1e-4);
+    }
+
+    #[test]
+    fn fixed_gain_estimator() {
+        let td = 0.1;
+        let C = matrix![1., 0.];
+        let R = matrix![0.5];
+        let u = matrix![0.; -9.81 * td];
+        let mut filter = gravity_filter(td);
+
+        let mut estimator: FixedGainEstimator<2, 2, 1, f32> =
+            filter.export_fixed_gain_estimator(&C, &R, 1e-6, 1000).unwrap();
+
+        // Outputs match once the full filter has converged
+        let mut true_pos = 0.;
+        let mut true_vel = 0.;
+        for k in 0..200 {
+            true_vel -= 9.81 * td;
+            true_pos += true_vel * td;
+            let y = matrix![true_pos + (random::<f32>() - 0.5)];
+
+            filter.predict_with_input(u);
+            filter.update(&C, &R, &y).unwrap();
+            let x = estimator.step(u, &y);
+            if k > 150 {
+                assert!((filter.get_state() - x).norm() < 1e-2);
+            }
+        }
+    }
+
+    #[test]
+    fn per_component_gating() {
+        let C = matrix![1., 0.; 0., 1.; 1., 0.];
+        let R = SMatrix::<f32, 3, 3>::identity() * 0.1;
+        let y = matrix![1.; 0.5; 1000.];
+
+        let mut filter = gravity_filter(0.1);
+        let accepted = filter.update_gated_components(&C, &R, &y, &[3.; 3]);
+        assert_eq!(accepted, [true, true, false]);
+
+        // Same as only applying the two good channels
+        let mut reference = gravity_filter(0.1);
+        reference.update(&matrix![1., 0.; 0., 1.], &(SMatrix::identity() * 0.1), &matrix![1.; 0.5]).unwrap();
+        assert!((filter.get_state() - reference.get_state()).norm() < 1e-5);
+    }
+
+    #[test]
+    fn reset_to_steady_state() {
+        let C = matrix![1., 0.];
+        let R = matrix![0.5];
+        let mut filter = gravity_filter(0.1);
+        for _ in 0..20 { filter.predict() }
+
+        let P = filter.steady_state_covariance(&C, &R, 1e-6, 1000).unwrap();
+        assert!(filter.reset_to_steady_state(matrix![3.; -1.], &C, &R, 1e-6, 1000));
+        assert_eq!(filter.get_state(), matrix![3.; -1.]);
+        assert!((filter.cross_covariance(&[0, 1], &[0, 1]) - P).norm() < 1e-4);
+
+        // Another predict and update cycle leaves the covariance at steady state
+        filter.update(&C, &R, &matrix![3.]).unwrap();
+        filter.predict();
+        assert!((filter.cross_covariance(&[0, 1], &[0, 1]) - P).norm() < 1e-4);
+
+        // The filter converges to the steady state with its inflated noise
+        let mut filter = gravity_filter(0.1);
+        filter.set_measurement_inflation(2.);
+        filter.set_forgetting_factor(1.05);
+        let P = filter.steady_state_covariance(&C, &R, 1e-6, 1000).unwrap();
+        for _ in 0..500 {
+            filter.update(&C, &R, &matrix![0.]).unwrap();
+            filter.predict();
+        }
+        assert!((filter.cross_covariance(&[0, 1], &[0, 1]) - P).norm() < 1e-3);
+    }
+
+    #[test]
+    fn expected_log_likelihood() {
+        let filter = gravity_filter(0.1);
+        let C = matrix![1., 0.];
+        let sharp = filter.expected_log_likelihood(&C, &matrix![0.01]).unwrap();
+        let blunt = filter.expected_log_likelihood(&C, &matrix![10.]).unwrap();
+        assert!(sharp > blunt);
+
+        // Entropy of a scalar Gaussian with variance S = 1 + 10
+        let entropy = 0.5 * (2. * core::f32::consts::PI * core::f32::consts::E * 11.).ln();
+        assert_approx_eq!(blunt, -entropy, 1e-5);
+    }
+
+    #[test]
+    fn blend_toward_reference() {
+        let blended = |weight: f32| {
+            let mut filter = gravity_filter(0.1);
+            filter.blend_toward(&matrix![4.; -2.], weight);
+            filter
+        };
+
+        assert_eq!(blended(0.).get_state(), matrix![0.; 0.]);
+        assert!((blended(0.25).get_state() - matrix![1.; -0.5]).norm() < 1e-6);
+        assert!((blended(0.5).get_state() - matrix![2.; -1.]).norm() < 1e-6);
+
+        // Covariance is inflated by the spread between the estimate and the reference
+        let variance = blended(0.5).linear_combination_variance(&matrix![1.; 0.]);
+        assert_approx_eq!(variance, 1. + 0.25 * 16., 1e-5);
+    }
+
+    #[test]
+    fn recent_measurement_sensitivity() {
+        let mut filter = gravity_filter(0.1);
+        let mut window = SensitivityWindow::<5, f32>::new();
+        window.record(&filter);
+
+        // Informative burst
+        for _ in 0..5 {
+            filter.update(&matrix![1., 0.; 0., 1.], &(SMatrix::identity() * 0.1), &matrix![0.; 0.]).unwrap();
+            filter.predict();
+            window.record(&filter);
+        }
+        let burst = window.recent_measurement_sensitivity();
+
+        // Prediction-dominated stretch
+        for _ in 0..5 {
+            filter.predict();
+            window.record(&filter);
+        }
+        let quiet = window.recent_measurement_sensitivity();
+
+        assert!(burst > 1.);
+        assert_eq!(quiet, 0.);
+    }
+
+    #[test]
+    fn dual_rate_gravity_fall() {
+        // Predict at 1 kHz, update position at 10 Hz
+        const G: f32 = 9.82;
+        let hz = 1000;
+        let td: f32 = 1. / hz as f32;
+        let seconds = 5;
+
+        let mut mean_pos = 0.;
+        let mut mean_vel = 0.;
+        for _ in 0..10 {
+            let mut filter = DualRateFilter::new(gravity_filter(td), 100);
+            let mut updates = 0;
+            for i in 0..hz * seconds {
+                let updated = filter.update_if_due(&matrix![1., 0.], &matrix![1.], || {
+                    let s = i as f32 * td;
+                    matrix![G * 0.5 * s.powf(2.0) + (random::<f32>() - 0.5)]
+                });
+                updates += updated.unwrap() as usize;
+                filter.predict_with_input(matrix![0.5 * td.powf(2.0) * G; td * G]);
+            }
+            assert_eq!(updates, 10 * seconds);
+
+            mean_pos += filter.get_state()[0] / 10.;
+            mean_vel += filter.get_state()[1] / 10.;
+        }
+
+        assert_approx_eq!(mean_pos, G * 0.5 * (seconds as f32).powf(2.0), 1.);
+        assert_approx_eq!(mean_vel, G * seconds as f32, 1.);
+    }
+
+    #[test]
+    fn dual_rate_step_cadence() {
+        let C = matrix![1., 0.];
+        let R = matrix![1.];
+        let u = matrix![0.; 0.];
+        let mut filter = DualRateFilter::new(gravity_filter(0.01), 4);
+
+        // With a measurement every fast step, updates happen every `ratio` steps
+        let updated: std::vec::Vec<bool> = (0..12).map(|_| filter.step(u, &C, &R, Some(&matrix![1.])).unwrap()).collect();
+        let expected: std::vec::Vec<bool> = (0..12).map(|k| k % 4 == 0).collect();
+        assert_eq!(updated, expected);
+
+        // A missing measurement leaves the update due until one arrives
+        assert!(!filter.step(u, &C, &R, None).unwrap());
+        assert!(filter.is_update_due());
+        assert!(filter.step(u, &C, &R, Some(&matrix![1.])).unwrap());
+        assert!(!filter.is_update_due());
+    }
+
+    #[test]
+    fn sensor_innovation_rms() {
+        let mut filter = KalmanFilter::<1, 1, f64>::new(matrix![1.], None, matrix![1e-4], matrix![0.], matrix![1.]);
+        let mut health = SensorHealth::<2, f64>::new();
+        assert!(health.register("good"));
+        assert!(health.register("biased"));
+        assert!(!health.register("third"));
+
+        // Alternating sensors, where the good sensor is the most frequent
+        let C = matrix![1.];
+        let R = matrix![0.1];
+        for k in 0..1000 {
+            if k % 5 == 0 {
+                health.update(&mut filter, "biased", &C, &R, &matrix![2. + random::<f64>() - 0.5]).unwrap();
+            } else {
+                health.update(&mut filter, "good", &C, &R, &matrix![1. + random::<f64>() - 0.5]).unwrap();
+            }
+            filter.predict();
+        }
+
+        let good = health.sensor_innovation_rms("good").unwrap();
+        let biased = health.sensor_innovation_rms("biased").unwrap();
+        assert!(biased > good);
+        assert_eq!(health.sensor_innovation_rms("third"), None);
+
+        // Failed updates are not recorded
+        let mut singular = KalmanFilter::<1, 1, f64>::new(matrix![1.], None, matrix![0.], matrix![0.], matrix![0.]);
+        let mut health = SensorHealth::<1, f64>::new();
+        assert!(health.register("good"));
+        assert!(health.update(&mut singular, "good", &C, &matrix![0.], &matrix![1.]).is_err());
+        assert_eq!(health.sensor_innovation_rms("good"), None);
+    }
+
+    #[test]
+    fn borrowed_shared_model() {
+        // One model shared between two filters
+        let td = 0.1;
+        let A = matrix![1., td; 0., 1.];
+        let B = SMatrix::<f32, 2, 2>::identity();
+        let Q = SMatrix::identity();
+
+        let mut first = kalman_borrowed::KalmanFilter::new(&A, Some(&B), &Q, matrix![0.; 0.], SMatrix::identity());
+        let mut second = kalman_borrowed::KalmanFilter::new(&A, Some(&B), &Q, matrix![0.; 0.], SMatrix::identity());
+        let mut owned = gravity_filter(td);
+
+        let u = matrix![0.; -9.81 * td];
+        for _ in 0..10 {
+            first.predict_with_input(u);
+            second.predict_with_input(u);
+            owned.predict_with_input(u);
+        }
+        assert_eq!(first.get_state(), second.get_state());
+        assert_eq!(first.get_covariance(), second.get_covariance());
+        assert!((first.get_state() - owned.get_state()).norm() < 1e-5);
+
+        let C = matrix![1., 0.];
+        first.update(&C, &matrix![0.5], &matrix![-1.]).unwrap();
+        owned.update(&C, &matrix![0.5], &matrix![-1.]).unwrap();
+        assert!((first.get_state() - owned.get_state()).norm() < 1e-5);
+    }
+
+    #[test]
+    fn preference_resolves_singular_innovation() {
+        // Two redundant noiseless measurements of the sum of the states
+        let C = matrix![1., 1.; 1., 1.];
+        let R = SMatrix::zeros();
+        let y = matrix![2.; 2.];
+
+        // Minimum-norm solution splits the correction evenly
+        let mut filter = gravity_filter(0.1);
+        filter.update_with_preference(&C, &R, &y, &SMatrix::identity()).unwrap();
+        assert!((filter.get_state() - matrix![1.; 1.]).norm() < 1e-4);
+
+        // Preferring corrections of the first state only
+        let mut filter = gravity_filter(0.1);
+        filter.update_with_preference(&C, &R, &y, &matrix![1., 0.; 0., 0.]).unwrap();
+        assert!((filter.get_state() - matrix![2.; 0.]).norm() < 1e-4);
+
+        // Regular innovation covariance is a normal update
+        let mut filter = gravity_filter(0.1);
+        let mut reference = gravity_filter(0.1);
+        filter.update_with_preference(&matrix![1., 0.], &matrix![0.5], &matrix![1.], &matrix![0., 0.; 0., 1.]).unwrap();
+        reference.update(&matrix![1., 0.], &matrix![0.5], &matrix![1.]).unwrap();
+        assert_eq!(filter.get_state(), reference.get_state());
+    }
+
+    #[test]
+    fn simulate_covariance_schedule() {
+        let position = (matrix![1., 0.], matrix![0.5]);
+        let velocity = (matrix![0., 1.], matrix![0.1]);
+        let schedule = [
+            CovarianceStep::Update { C: &position.0, R: &position.1 },
+            CovarianceStep::Predict,
+            CovarianceStep::Predict,
+            CovarianceStep::Update { C: &velocity.0, R: &velocity.1 },
+            CovarianceStep::Predict,
+            CovarianceStep::Update { C: &position.0, R: &position.1 },
+        ];
+
+        // Nominal filter, and with a forgetting factor and a variance ceiling
+        let mut fading = gravity_filter(0.1);
+        fading.set_forgetting_factor(1.5);
+        fading.set_variance_ceiling(matrix![10.; 1.2]);
+        for mut filter in [gravity_filter(0.1), fading] {
+            let mut simulated = [SMatrix::zeros(); 6];
+            assert_eq!(filter.simulate_covariance(&schedule, &mut simulated), 6);
+
+            // Real run with arbitrary measurement values
+            for (step, P) in schedule.iter().zip(simulated) {
+                match step {
+                    CovarianceStep::Predict => filter.predict(),
+                    CovarianceStep::Update { C, R } => filter.update(*C, *R, &matrix![random::<f32>()]).unwrap(),
+                }
+                assert!((filter.cross_covariance(&[0, 1], &[0, 1]) - P).norm() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn sequential_updates_equal_stacked_update() {
+        let td = 0.1;
+        let mut sequential = gravity_filter(td);
+        let mut stacked = gravity_filter(td);
+        for _ in 0..5 {
+            sequential.predict();
+            stacked.predict();
+        }
+
+        let C = matrix![1., 0.; 0., 1.; 1., 1.];
+        let R = matrix![0.5, 0., 0.; 0., 0.2, 0.; 0., 0., 1.];
+        let y = matrix![1.; -2.; 0.5];
+
+        // Three Ny=1 updates between predictions
+        for i in 0..3 {
+            let C_i: SMatrix<f32, 1, 2> = C.fixed_rows::<1>(i).into_owned();
+            sequential.update(&C_i, &matrix![R[(i, i)]], &matrix![y[i]]).unwrap();
+        }
+
+        // One stacked Ny=3 update
+        stacked.update(&C, &R, &y).unwrap();
+
+        assert!((sequential.get_state() - stacked.get_state()).norm() < 1e-5);
+        let P_seq = sequential.cross_covariance(&[0, 1], &[0, 1]);
+        let P_stack = stacked.cross_covariance(&[0, 1], &[0, 1]);
+        assert!((P_seq - P_stack).norm() < 1e-5);
+
+        // And the predictions from both remain equal
+        sequential.predict();
+        stacked.predict();
+        assert!((sequential.get_state() - stacked.get_state()).norm() < 1e-5);
+    }
+
+    #[test]
+    fn fisher_information() {
+        let mut filter = gravity_filter(0.1);
+        for _ in 0..3 { filter.predict() }
+
+        let C = matrix![1., 0.; 1., 1.];
+        let R = matrix![0.5, 0.1; 0.1, 0.3];
+        let fisher = filter.fisher_information(&C, &R).unwrap();
+        let (_, prior) = filter.to_information_form().unwrap();
+
+        filter.update(&C, &R, &matrix![1.; 2.]).unwrap();
+        let (_, posterior) = filter.to_information_form().unwrap();
+        assert!((prior + fisher - posterior).norm() < 1e-3);
+    }
+
+    #[test]
+    fn initialize_from_first_measurement() {
+        let td = 0.1;
+        let C = matrix![1., 0.];
+        let R = matrix![0.01];
+        let u = matrix![0.; -9.81 * td];
+        let filter = || KalmanFilter::<2, 2, f32>::new(
+            matrix![1., td; 0., 1.],
+            Some(SMatrix::identity()),
+            SMatrix::identity() * 1e-4,
+            matrix![0.; 0.],
+            SMatrix::identity(),
+        );
+
+        let mut guessed = filter();
+        let mut initialized = filter();
+        assert!(initialized.initialize_from_measurement(&C, &R, &matrix![100.], 100.));
+        assert_approx_eq!(initialized.get_state()[0], 100., 1e-4);
+        assert_approx_eq!(initialized.linear_combination_variance(&matrix![1.; 0.]), 0.01, 1e-5);
+        assert_approx_eq!(initialized.linear_combination_variance(&matrix![0.; 1.]), 100., 1e-3);
+
+        // Tracks immediately, while the guessed initial state has a large transient
+        let mut true_pos = 100.;
+        let mut true_vel = 0.;
+        for _ in 0..5 {
+            true_pos += true_vel * td;
+            true_vel -= 9.81 * td;
+            guessed.predict_with_input(u);
+            initialized.predict_with_input(u);
+            guessed.update(&C, &R, &matrix![true_pos]).unwrap();
+            initialized.update(&C, &R, &matrix![true_pos]).unwrap();
+        }
+        assert!((initialized.get_state()[0] - true_pos).abs() < 0.01);
+        assert!((initialized.get_state()[1] - true_vel).abs() < 0.01);
+        assert!((guessed.get_state()[1] - true_vel).abs() > 1.);
+    }
+
+    #[test]
+    fn unobservable_subspace() {
+        // Two independent random walks where only their difference is measured,
+        // leaving their sum unobservable
+        let filter = KalmanFilter::<2, 1, f32>::new(
+            SMatrix::identity(),
+            None,
+            SMatrix::identity(),
+            SMatrix::zeros(),
+            SMatrix::identity(),
+        );
+        let (dimension, basis) = filter.unobservable_subspace(&matrix![1., -1.]);
+        assert_eq!(dimension, 1);
+        let direction = basis.column(0);
+        assert_approx_eq!(direction[0].abs(), core::f32::consts::FRAC_1_SQRT_2, 1e-5);
+        assert_approx_eq!(direction[0], direction[1], 1e-5);
+
+        // Position measurements of the gravity model observe everything
+        let (dimension, _) = gravity_filter(0.1).unobservable_subspace(&matrix![1., 0.]);
+        assert_eq!(dimension, 0);
+
+        // Velocity measurements cannot determine the position
+        let (dimension, basis) = gravity_filter(0.1).unobservable_subspace(&matrix![0., 1.]);
+        assert_eq!(dimension, 1);
+        assert_approx_eq!(basis[(0, 0)].abs(), 1., 1e-5);
+    }
+
+    #[test]
+    fn predicted_covariance_at_horizon() {
+        let mut filter = gravity_filter(0.1);
+        filter.update(&matrix![1., 0.], &matrix![0.1], &matrix![0.]).unwrap();
+        let u = matrix![0.; -0.981];
+
+        for steps in [0, 1, 5, 20] {
+            let mut clone = filter.clone();
+            for _ in 0..steps { clone.predict_with_input(u) }
+            let P = filter.predicted_covariance_at(steps, &u);
+            assert!((P - clone.cross_covariance(&[0, 1], &[0, 1])).norm() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn checked_initial_covariance() {
+        let A = matrix![1., 0.1; 0., 1.];
+        let Q = SMatrix::<f32, 2, 2>::identity();
+        let x = matrix![0.; 0.];
+
+        // Indefinite, with eigenvalues 3 and -1
+        let P_bad = matrix![1., 2.; 2., 1.];
+        let result = KalmanFilter::<2, 2, f32>::new_checked(A, None, Q, x, P_bad, 1e3);
+        assert_eq!(result.err(), Some(KalmanError::InvalidInitialCovariance));
+        assert!(KalmanFilter::<2, 2, f32>::new_checked(A, None, Q, x, SMatrix::identity() * 1e6, 1e3).is_err());
+        assert!(KalmanFilter::<2, 2, f32>::new_checked(A, None, Q, x, SMatrix::identity(), 1e3).is_ok());
+
+        // Projection keeps the valid eigen-direction and clamps the other
+        let (filter, projected) = KalmanFilter::<2, 2, f32>::new_projected(A, None, Q, x, P_bad, 1e-3);
+        assert!(projected);
+        let P = filter.cross_covariance(&[0, 1], &[0, 1]);
+        assert!(P.cholesky().is_some());
+        let valid = matrix![1.5, 1.5; 1.5, 1.5];
+        assert!((P - valid).norm() < 1e-2);
+
+        let (_, projected) = KalmanFilter::<2, 2, f32>::new_projected(A, None, Q, x, SMatrix::identity(), 1e-3);
+        assert!(!projected);
+    }
+
+    #[test]
+    fn covariance_shrinks_after_update() {
+        let mut filter = gravity_filter(0.1);
+        for _ in 0..5 { filter.predict() }
+        let predicted = filter.get_covariance();
+
+        filter.update(&matrix![1., 0.], &matrix![0.1], &matrix![0.]).unwrap();
+        let updated = filter.get_covariance();
+        assert!(updated[(0, 0)] < predicted[(0, 0)]);
+        assert!(updated[(1, 1)] < predicted[(1, 1)]);
+        assert!(updated.trace() < predicted.trace());
+        assert_eq!(updated, updated.transpose());
+    }
+
+    #[test]
+    fn merge_tracks() {
+        let C = matrix![1., 0.];
+        let mut a = gravity_filter(0.1);
+        a.update(&C, &matrix![0.5], &matrix![1.]).unwrap();
+        let mut b = gravity_filter(0.1);
+        b.update(&C, &matrix![1.], &matrix![2.]).unwrap();
+
+        let merged = a.merge_with(&b).unwrap();
+        let P = merged.get_covariance();
+        assert!(P[(0, 0)] < a.get_covariance()[(0, 0)]);
+        assert!(P[(0, 0)] < b.get_covariance()[(0, 0)]);
+        assert!(P[(1, 1)] < a.get_covariance()[(1, 1)]);
+
+        // Position weighted by the inverse variances 1/3 and 1/2
+        let expected = (a.get_state()[0] * 3. + b.get_state()[0] * 2.) / 5.;
+        assert_approx_eq!(merged.get_state()[0], expected, 1e-4);
+    }
+
+    #[test]
+    fn joseph_form_stays_positive() {
+        let mut filter = gravity_filter(0.01);
+        let mut reference = gravity_filter(0.01);
+        let C = matrix![1., 0.];
+        let R = matrix![100.];
+        for k in 0..5000 {
+            let y = matrix![(k as f32 * 0.01).sin() + random::<f32>() - 0.5];
+            filter.predict();
+            filter.update_joseph(&C, &R, &y).unwrap();
+            reference.predict();
+            reference.update(&C, &R, &y).unwrap();
+
+            let eigenvalues = filter.get_covariance().symmetric_eigenvalues();
+            assert!(eigenvalues.iter().all(|&e| e >= 0.));
+        }
+
+        // Same estimate as the standard form for the optimal gain
+        assert!((filter.get_state() - reference.get_state()).norm() < 1e-2);
+    }
+
+    #[test]
+    fn trace_callback() {
+        use std::sync::Mutex;
+        use std::vec::Vec;
+
+        // Gains and posterior variances of traced updates, and count of traced predictions
+        static UPDATES: Mutex<Vec<(f32, f32)>> = Mutex::new(Vec::new());
+        static PREDICTS: Mutex<usize> = Mutex::new(0);
+        fn record(event: &TraceEvent<'_, f32>) {
+            match event {
+                TraceEvent::Predict { .. } => *PREDICTS.lock().unwrap() += 1,
+                TraceEvent::Update { y_res, S, K, x, P } => {
+                    assert_eq!((y_res.len(), S.len(), K.len(), x.len(), P.len()), (1, 1, 2, 2, 4));
+                    UPDATES.lock().unwrap().push((K[0], P[0]));
+                }
+            }
+        }
+
+        let mut filter = gravity_filter(0.1);
+        filter.set_trace(Some(record));
+        filter.update(&matrix![1., 0.], &matrix![1.], &matrix![2.]).unwrap();
+        filter.predict();
+        filter.update_sequential(&matrix![1., 0.], &matrix![1.], &matrix![2.]).unwrap();
+        filter.update_with_state_mask(&matrix![1., 0.], &matrix![1.], &matrix![2.], &[true, false]).unwrap();
+        filter.predict_backward(matrix![0.; 0.]).unwrap();
+        filter.set_trace(None);
+        filter.predict();
+
+        // With P = I and R = 1, the gain on position is 0.5 and the variance halves
+        assert_eq!(UPDATES.lock().unwrap()[0], (0.5, 0.5));
+        assert_eq!(UPDATES.lock().unwrap().len(), 3);
+        assert_eq!(*PREDICTS.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn steady_state_innovation_covariance() {
+        let C = matrix![1., 0.; 0., 1.];
+        let R = matrix![0.5, 0.; 0., 2.];
+        let mut filter = gravity_filter(0.1);
+        let S_inf = filter.steady_state_innovation_covariance(&C, &R, 1e-5, 1000).unwrap();
+
+        for _ in 0..200 {
+            filter.predict();
+            filter.update(&C, &R, &matrix![random::<f32>(); random::<f32>()]).unwrap();
+        }
+        filter.predict();
+        let S = C * filter.get_covariance() * C.transpose() + R;
+        assert!((S - S_inf).norm() < 1e-4);
+    }
+
+    #[test]
+    fn set_Q_between_predicts() {
+        let mut filter = gravity_filter(0.1);
+        filter.predict();
+        let before = filter.get_covariance();
+
+        // Growth of the prior covariance over one prediction is `A*P*A^T + Q - P`
+        let A = matrix![1., 0.1; 0., 1.];
+        filter.set_Q(SMatrix::identity() * 10.);
+        filter.predict();
+        let expected = A * before * A.transpose() + SMatrix::<f32, 2, 2>::identity() * 10.;
+        assert!((filter.get_covariance() - expected).norm() < 1e-5);
+
+        // Also between an update and a prediction
+        filter.update(&matrix![1., 0.], &matrix![1.], &matrix![0.]).unwrap();
+        let posterior = filter.get_covariance();
+        filter.set_Q(SMatrix::zeros());
+        filter.predict();
+        assert!((filter.get_covariance() - A * posterior * A.transpose()).norm() < 1e-5);
+    }
+
+    #[test]
+    fn singular_innovation_is_reported() {
+        let mut filter = gravity_filter(0.01);
+        let x = filter.get_state();
+        let P = filter.get_covariance();
+
+        // A degenerate output with noiseless measurement gives a zero innovation covariance
+        let result = filter.update(&matrix![0., 0.], &matrix![0.], &matrix![1.]);
+        assert_eq!(result, Err(KalmanError::SingularInnovation));
+        assert_eq!(filter.get_state(), x);
+        assert_eq!(filter.get_covariance(), P);
+
+        // Likewise for the other linear and extended filters
+        let mut classic = kalman_classic::KalmanFilter::new(
+            matrix![1.], matrix![0.], matrix![0.], matrix![1.], matrix![0.], matrix![2.], matrix![1.],
+        );
+        assert_eq!(classic.update(&matrix![1.]), Err(KalmanError::SingularInnovation));
+        assert_eq!(classic.get_state(), matrix![2.]);
+
+        let (A, Q) = (matrix![1.], matrix![1.]);
+        let mut borrowed = kalman_borrowed::KalmanFilter::<1, 1, f32>::new(&A, None, &Q, matrix![2.], matrix![1.]);
+        assert_eq!(borrowed.update(&matrix![0.], &matrix![0.], &matrix![1.]), Err(KalmanError::SingularInnovation));
+        assert_eq!(borrowed.get_state(), matrix![2.]);
+
+        let mut extended = kalman_extended::KalmanFilter::new(
+            pendulum_f, pendulum_fj, angle_h, angle_hj,
+            SMatrix::zeros(),
+            matrix![-0.01],
+            matrix![1.0; 0.],
+            matrix![0.01, 0.; 0., 0.01],
+        );
+        assert_eq!(extended.update(&matrix![1.]), Err(KalmanError::SingularInnovation));
+        assert_eq!(extended.get_state(), matrix![1.; 0.]);
+    }
+
+    #[test]
+    fn state_only_update_with_steady_gain() {
+        let td = 0.1;
+        let C = matrix![1., 0.];
+        let R = matrix![0.5];
+        let u = matrix![0.; -9.81 * td];
+        let mut filter = gravity_filter(td);
+        let mut observer = gravity_filter(td);
+
+        let P = filter.steady_state_covariance(&C, &R, 1e-6, 1000).unwrap();
+        let K = P * C.transpose() * (C * P * C.transpose() + R).try_inverse().unwrap();
+
+        // State trajectories match once the full filter has converged
+        let mut true_pos = 0.;
+        let mut true_vel = 0.;
+        for k in 0..200 {
+            true_vel -= 9.81 * td;
+            true_pos += true_vel * td;
+            let y = matrix![true_pos + (random::<f32>() - 0.5)];
+
+            filter.predict_with_input(u);
+            filter.update(&C, &R, &y).unwrap();
+            observer.predict_with_input(u);
+            observer.update_state_only(&C, &K, &y);
+            if k > 150 {
+                assert!((filter.get_state() - observer.get_state()).norm() < 1e-2);
+            }
+        }
+
+        // The covariance is left to the open-loop prediction
+        assert!(observer.get_covariance()[(0, 0)] > 100.);
+    }
+
+    #[test]
+    fn gate_rejects_spike() {
+        let C = matrix![1., 0.];
+        let R = matrix![0.01];
+        let mut filter = gravity_filter(0.1);
+        for k in 0..50 {
+            filter.predict();
+            filter.update_gated(&C, &R, &matrix![0.05 * ((k % 3) as f32 - 1.)], 6.63);
+        }
+
+        // A 50-sigma spike is rejected and leaves the estimate untouched
+        let x = filter.get_state();
+        let S = (C * filter.get_covariance() * C.transpose() + R)[0];
+        assert!(!filter.update_gated(&C, &R, &matrix![x[0] + 50. * S.sqrt()], 6.63));
+        assert!((filter.get_state() - x).norm() < 1e-6);
+    }
+
+    #[test]
+    fn forecast_envelope_grows() {
+        let td = 0.1;
+        let u = matrix![0.; -9.81 * td];
+        let filter = gravity_filter(td);
+
+        let mut envelope = [(matrix![0.; 0.], matrix![0.; 0.]); 20];
+        filter.forecast_envelope(&u, &mut envelope);
+
+        let mut last = filter.get_standard_deviations();
+        for (_, std) in envelope {
+            assert!(std[0] > last[0] && std[1] > last[1]);
+            last = std;
+        }
+
+        // The envelope ends at the forecast horizon
+        let (x, P) = filter.predict_n(20, &u);
+        assert_eq!(envelope[19].0, x);
+        assert_approx_eq!(envelope[19].1[0], P[(0, 0)].sqrt(), 1e-6);
+    }
+
+    #[test]
+    fn accumulated_measurements_match_stacked_update() {
+        let C1 = matrix![1., 0.];
+        let R1 = matrix![0.5];
+        let C2 = matrix![1., 0.; 0., 1.];
+        let R2 = matrix![0.2, 0.05; 0.05, 0.3];
+
+        let mut filter = gravity_filter(0.1);
+        filter.accumulate(&C1, &R1, &matrix![1.]).unwrap();
+        filter.accumulate(&C2, &R2, &matrix![1.2; -0.4]).unwrap();
+        filter.predict();
+
+        let mut stacked = gravity_filter(0.1);
+        stacked.update(
+            &matrix![1., 0.; 1., 0.; 0., 1.],
+            &matrix![0.5, 0., 0.; 0., 0.2, 0.05; 0., 0.05, 0.3],
+            &matrix![1.; 1.2; -0.4],
+        ).unwrap();
+        stacked.predict();
+
+        assert!((filter.get_state() - stacked.get_state()).norm() < 1e-5);
+        assert!((filter.get_covariance() - stacked.get_covariance()).norm() < 1e-5);
+
+        // Nothing is pending after the prediction
+        filter.predict();
+        stacked.predict();
+        assert!((filter.get_state() - stacked.get_state()).norm() < 1e-5);
+    }
+
+    #[test]
+    fn nonlinear_radar_tracking() {
+        let td = 1.0;
+        let A = matrix![
+            1., 0., td, 0.;
+            0., 1., 0., td;
+            0., 0., 1., 0.;
+            0., 0., 0., 1.];
+        let f = |x: &SMatrix<f64, 4, 1>| A * x;
+        let F_jac = |_: &SMatrix<f64, 4, 1>| A;
+
+        // Range and bearing to the target from a radar at the origin
+        let h = |x: &SMatrix<f64, 4, 1>| matrix![x[0].hypot(x[1]); x[1].atan2(x[0])];
+        let H = |x: &SMatrix<f64, 4, 1>| {
+            let r2 = x[0] * x[0] + x[1] * x[1];
+            let r = r2.sqrt();
+            matrix![
+                x[0] / r, x[1] / r, 0., 0.;
+                -x[1] / r2, x[0] / r2, 0., 0.]
+        };
+        let R = matrix![25., 0.; 0., 2.5e-5];
+
+        let mut filter: kalman_nonlinear::KalmanFilter<4, 1, f64> = kalman_nonlinear::KalmanFilter::new(
+            SMatrix::identity() * 0.01,
+            matrix![900.; 600.; 0.; 0.],
+            SMatrix::from_diagonal(&matrix![1e4; 1e4; 100.; 100.]),
+        );
+
+        // Known straight-line trajectory of the target
+        let truth = |k: f64| matrix![1000. - 10. * k * td; 500. + 5. * k * td; -10.; 5.];
+        for k in 1..=100 {
+            filter.predict(f, F_jac);
+            let noise = matrix![(random::<f64>() - 0.5) * 17.; (random::<f64>() - 0.5) * 0.017];
+            filter.update(h, H, &R, &(h(&truth(k as f64)) + noise)).unwrap();
+        }
+
+        let error = filter.get_state() - truth(100.);
+        assert!(error.fixed_rows::<2>(0).norm() < 10.);
+        assert!(error.fixed_rows::<2>(2).norm() < 1.);
+    }
+
+    #[test]
+    fn kl_divergence_of_update() {
+        let C = matrix![1., 0.];
+        let mut filter = gravity_filter(0.1);
+        filter.predict();
+        assert!(filter.update_kl_divergence().is_none());
+
+        // An uninformative measurement barely changes the distribution
+        filter.update(&C, &matrix![1e6], &matrix![0.5]).unwrap();
+        assert!(filter.update_kl_divergence().unwrap() < 1e-3);
+
+        // A precise measurement far from the estimate is highly informative
+        let mut filter = gravity_filter(0.1);
+        filter.predict();
+        filter.update(&C, &matrix![1e-4], &matrix![10.]).unwrap();
+        assert!(filter.update_kl_divergence().unwrap() > 10.);
+    }
+
+    #[test]
+    fn variance_ceiling_saturates() {
+        let mut filter = gravity_filter(0.1);
+        filter.set_variance_ceiling(matrix![50.; 20.]);
+        for _ in 0..1000 {
+            filter.predict();
+        }
+
+        let P = filter.get_covariance();
+        assert_approx_eq!(P.trace(), 70., 1e-3);
+        assert!(P.cholesky().is_some());
+
+        // Without the ceiling the covariance keeps growing
+        filter.clear_variance_ceiling();
+        filter.predict();
+        assert!(filter.get_covariance().trace() > 70.);
+    }
+
+    #[test]
+    fn unscented_pendulum() {
+        let td = 0.01;
+        let f = |x: &SMatrix<f64, 2, 1>| matrix![x[0] + x[1] * td; x[1] - 9.81 * x[0].sin() * td];
+
+        // Horizontal position of the bob of a unit-length pendulum
+        let h = |x: &SMatrix<f64, 2, 1>| matrix![x[0].sin()];
+        let R = matrix![0.05f64.powi(2) / 3.];
+
+        let mut filter: kalman_unscented::KalmanFilter<2, 1, f64> = kalman_unscented::KalmanFilter::new(
+            matrix![1e-8, 0.; 0., 1e-6],
+            matrix![0.5; 0.],
+            matrix![0.5, 0.; 0., 1.],
+            1e-3,
+            2.,
+            0.,
+        );
+
+        let mut truth = matrix![1.; 0.];
+        for _ in 0..1000 {
+            truth = f(&truth);
+            filter.predict(f).unwrap();
+            filter.update(h, &R, &(h(&truth) + matrix![(random::<f64>() - 0.5) * 0.1])).unwrap();
+        }
+
+        let error = filter.get_state() - truth;
+        assert!(error[0].abs() < 0.02);
+        assert!(error[1].abs() < 0.1);
+        assert!(filter.get_covariance().cholesky().is_some());
+    }
+
+    #[test]
+    fn square_root_matches_standard() {
+        let td = 0.1;
+        let A = matrix![1., td; 0., 1.];
+        let Q = matrix![1e-4, 0.; 0., 1e-2];
+        let mut filter: KalmanFilter<2, 1, f64> = KalmanFilter::new(A, None, Q, matrix![0.; 0.], SMatrix::identity());
+        let mut sqrt_filter: kalman_sqrt::KalmanFilter<2, 1, f64> =
+            kalman_sqrt::KalmanFilter::new(A, None, Q, matrix![0.; 0.], SMatrix::identity());
+
+        let C = matrix![1., 0.; 1., 1.];
+        let R = matrix![0.5, 0.1; 0.1, 0.3];
+        for k in 0..100 {
+            let y = matrix![k as f64 * 0.1; 1.] + matrix![random::<f64>() - 0.5; random::<f64>() - 0.5];
+            filter.predict();
+            sqrt_filter.predict();
+            filter.update(&C, &R, &y).unwrap();
+            sqrt_filter.update(&C, &R, &y).unwrap();
+            assert!((filter.get_state() - sqrt_filter.get_state()).norm() < 1e-4);
+            assert!((filter.get_covariance() - sqrt_filter.get_covariance()).norm() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn square_root_stays_positive_definite() {
+        // Two nearly collinear measurements, far more precise than the initial uncertainty
+        let A = matrix![1., 0.; 0., 1.];
+        let P = matrix![1e4, 0.; 0., 1e4];
+        let C1 = matrix![1., 1.];
+        let C2 = matrix![1., 1.001];
+        let R = matrix![1e-4];
+
+        let mut filter: KalmanFilter<2, 1, f32> = KalmanFilter::new(A, None, SMatrix::zeros(), matrix![0.; 0.], P);
+        let mut sqrt_filter: kalman_sqrt::KalmanFilter<2, 1, f32> =
+            kalman_sqrt::KalmanFilter::new(A, None, SMatrix::zeros(), matrix![0.; 0.], P);
+        let mut reference: KalmanFilter<2, 1, f64> = KalmanFilter::new(A.cast(), None, SMatrix::zeros(), matrix![0.; 0.], P.cast());
+
+        for k in 0..20 {
+            let y1 = matrix![(k as f32 * 0.37).sin()];
+            let y2 = matrix![(k as f32 * 0.73).cos()];
+            filter.predict();
+            sqrt_filter.predict();
+            reference.predict();
+            for (C, y) in [(C1, y1), (C2, y2)] {
+                filter.update(&C, &R, &y).unwrap();
+                sqrt_filter.update(&C, &R, &y).unwrap();
+                reference.update(&C.cast(), &R.cast(), &y.cast()).unwrap();
+            }
+
+            let P = sqrt_filter.get_covariance();
+            assert!(P[(0, 0)] >= 0. && P[(0, 0)] * P[(1, 1)] >= P[(0, 1)] * P[(1, 0)]);
+        }
+
+        // The square-root filter stays close to the double precision reference
+        let error = (sqrt_filter.get_state().cast::<f64>() - reference.get_state()).norm();
+        let standard_error = (filter.get_state().cast::<f64>() - reference.get_state()).norm();
+        assert!(error < 0.5);
+        assert!(standard_error > 10. * error);
+        assert!((sqrt_filter.get_covariance().cast::<f64>() - reference.get_covariance()).norm() < 1e-2);
+    }
+
+    #[test]
+    fn information_form_matches_sequential_updates() {
+        let td = 0.1;
+        let A = matrix![1., td; 0., 1.];
+        let Q = matrix![1e-3, 0.; 0., 1e-2];
+        let mut filter: KalmanFilter<2, 1, f64> = KalmanFilter::new(A, None, Q, matrix![0.; 0.], SMatrix::identity());
+        let mut info_filter: kalman_information::KalmanFilter<2, 1, f64> =
+            kalman_information::KalmanFilter::new(A, None, Q, matrix![0.; 0.], SMatrix::identity());
+
+        // Three scalar sensors
+        let sensors = [
+            (matrix![1., 0.], matrix![0.5]),
+            (matrix![0., 1.], matrix![0.2]),
+            (matrix![1., 0.5], matrix![1.]),
+        ];
+        for k in 0..50 {
+            filter.predict();
+            info_filter.predict().unwrap();
+            for (C, R) in sensors.iter() {
+                let z = matrix![k as f64 * 0.1 + random::<f64>() - 0.5];
+                filter.update(C, R, &z).unwrap();
+                info_filter.update(C, R, &z).unwrap();
+            }
+            assert!((filter.get_state() - info_filter.get_state().unwrap()).norm() < 1e-5);
+            assert!((filter.get_covariance() - info_filter.get_covariance().unwrap()).norm() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn information_form_uninformative_prior() {
+        let td = 0.1;
+        let A = matrix![1., td; 0., 1.];
+        let B = matrix![0.; td];
+        let Q = matrix![1e-3, 0.; 0., 1e-2];
+        let mut info_filter: kalman_information::KalmanFilter<2, 1, f64> =
+            kalman_information::KalmanFilter::new(A, Some(B), Q, matrix![0.; 0.], SMatrix::zeros());
+
+        // Predicting from no information stays uninformative
+        info_filter.predict_with_input(matrix![-9.81]).unwrap();
+        assert_eq!(info_filter.get_information(), (SMatrix::zeros(), SMatrix::zeros()));
+        assert!(info_filter.get_state().is_none());
+
+        // The first update then fully determines the estimate
+        let R = matrix![0.5, 0.; 0., 0.2];
+        info_filter.update(&SMatrix::identity(), &R, &matrix![1.; 2.]).unwrap();
+        assert!((info_filter.get_state().unwrap() - matrix![1.; 2.]).norm() < 1e-12);
+        assert!((info_filter.get_covariance().unwrap() - R).norm() < 1e-12);
+
+        // Later predictions match the covariance form
+        let mut filter: KalmanFilter<2, 1, f64> = KalmanFilter::new(A, Some(B), Q, matrix![1.; 2.], R);
+        for _ in 0..10 {
+            filter.predict_with_input(matrix![-9.81]);
+            info_filter.predict_with_input(matrix![-9.81]).unwrap();
+        }
+        assert!((filter.get_state() - info_filter.get_state().unwrap()).norm() < 1e-9);
+        assert!((filter.get_covariance() - info_filter.get_covariance().unwrap()).norm() < 1e-9);
+    }
+
+    #[test]
+    fn builder_defaults() {
+        let A = matrix![1., 0.1; 0., 1.];
+        let Q = matrix![1e-3, 0.; 0., 1e-2];
+        let mut filter: KalmanFilter<2, 1, f32> = KalmanFilterBuilder::new()
+            .transition(A)
+            .process_noise(Q)
+            .build();
+
+        // Zero initial state with identity covariance
+        assert_eq!(filter.get_state(), matrix![0.; 0.]);
+        assert_eq!(filter.get_covariance(), matrix![1., 0.; 0., 1.]);
+
+        // The input has no effect with the default zero `B`
+        filter.predict_with_input(matrix![1.]);
+        assert_eq!(filter.get_state(), matrix![0.; 0.]);
+        assert_eq!(filter.get_covariance(), A * A.transpose() + Q);
+    }
+
+    #[test]
+    fn default_constructor() {
+        let mut filter: KalmanFilter<2, 1, f32> = Default::default();
+        assert_eq!(filter.get_state(), matrix![0.; 0.]);
+        assert_eq!(filter.get_covariance(), matrix![1., 0.; 0., 1.]);
+
+        // Identity `A` and zero `Q` leave the estimate unchanged
+        filter.predict();
+        assert_eq!(filter.get_covariance(), matrix![1., 0.; 0., 1.]);
+    }
+
+    #[test]
+    fn measurement_log_likelihood() {
+        let C = matrix![1., 0.];
+        let R = matrix![0.1];
+        let filter = gravity_filter(0.1);
+
+        let near = filter.measurement_log_likelihood(&C, &R, &matrix![0.01]).unwrap();
+        let off = filter.measurement_log_likelihood(&C, &R, &matrix![5.]).unwrap();
+        assert!(near > off + 10.);
+
+        // Gaussian density with `S = 1.1` at zero residual
+        let peak = filter.measurement_log_likelihood(&C, &R, &matrix![0.]).unwrap();
+        assert_approx_eq!(peak, -0.5 * (2. * core::f32::consts::PI * 1.1).ln(), 1e-6);
+    }
+
+    #[test]
+    fn consistency_diagnostics() {
+        let td = 0.1;
+        let G = 9.81;
+        let (q_pos, q_vel, r) = (1e-4, 1e-2, 0.25);
+        let mut filter = KalmanFilter::<2, 2, f64>::new(
+            matrix![1., td; 0., 1.],
+            Some(SMatrix::identity()),
+            matrix![q_pos, 0.; 0., q_vel],
+            matrix![0.; 0.],
+            matrix![q_pos, 0.; 0., q_vel],
+        );
+
+        // Uniform noise of variance `v`
+        let noise = |v: f64| (random::<f64>() - 0.5) * (12. * v).sqrt();
+
+        // Gravity-fall simulation with noise matching the model
+        let steps = 5000;
+        let u = matrix![-0.5 * G * td * td; -G * td];
+        let mut x_true: SMatrix<f64, 2, 1> = matrix![0.; 0.];
+        let (mut nis, mut nees) = (0., 0.);
+        for _ in 0..steps {
+            x_true = matrix![1., td; 0., 1.] * x_true + u + matrix![noise(q_pos); noise(q_vel)];
+            filter.predict_with_input(u);
+
+            let y = matrix![x_true[0] + noise(r)];
+            nis += filter.normalized_innovation_squared(&matrix![1., 0.], &matrix![r], &y).unwrap();
+            filter.update(&matrix![1., 0.], &matrix![r], &y).unwrap();
+            nees += filter.normalized_estimation_error_squared(&x_true).unwrap();
+        }
+
+        assert_approx_eq!(nis / steps as f64, 1., 0.15);
+        assert_approx_eq!(nees / steps as f64, 2., 0.4);
+    }
+
+    #[test]
+    fn forecast_falling_object() {
+        let td = 0.1;
+        let G = 9.81;
+        let u = matrix![-0.5 * G * td * td; -G * td];
+        let mut filter = gravity_filter(td);
+        filter.update(&matrix![1., 0.; 0., 1.], &matrix![0.01, 0.; 0., 0.01], &matrix![0.; 0.]).unwrap();
+        let (x, P) = (filter.get_state(), filter.get_covariance());
+
+        // Uncertainty grows with the forecast horizon
+        let mut last = P;
+        for steps in 1..=10 {
+            let (_, P) = filter.forecast(steps, &u);
+            assert!(P[(0, 0)] > last[(0, 0)] && P[(1, 1)] > last[(1, 1)]);
+            last = P;
+        }
+
+        // The true position lies within the uncertainty bound
+        let (x_forecast, P_forecast) = filter.forecast(10, &u);
+        let t = 10. * td;
+        assert!((x_forecast[0] + 0.5 * G * t * t).abs() < 3. * P_forecast[(0, 0)].sqrt());
+        assert!((x_forecast[1] + G * t).abs() < 3. * P_forecast[(1, 1)].sqrt());
+
+        // The live filter is untouched
+        assert_eq!(filter.get_state(), x);
+        assert_eq!(filter.get_covariance(), P);
+    }
+
+    #[test]
+    fn forgetting_factor_tracks_step_change() {
+        let td = 0.1;
+        let cv_filter = || KalmanFilter::<2, 1, f64>::new(
+            matrix![1., td; 0., 1.],
+            None,
+            matrix![1e-6, 0.; 0., 1e-6],
+            matrix![0.; 0.],
+            matrix![1., 0.; 0., 1.],
+        );
+        let mut filter = cv_filter();
+        let mut fading = cv_filter();
+        fading.set_forgetting_factor(1.2);
+
+        // Stationary target, which starts moving at an unmodeled constant velocity
+        let C = matrix![1., 0.];
+        let R = matrix![0.01];
+        let mut pos = 0.;
+        let mut errors = (0., 0.);
+        for k in 0..200 {
+            if k >= 100 { pos += 1. * td }
+            let y = matrix![pos];
+            filter.predict();
+            fading.predict();
+            filter.update(&C, &R, &y).unwrap();
+            fading.update(&C, &R, &y).unwrap();
+            if k >= 120 {
+                errors.0 += (filter.get_state()[1] - 1.).abs();
+                errors.1 += (fading.get_state()[1] - 1.).abs();
+            }
+        }
+        assert!(errors.1 < 0.1 * errors.0);
+    }
+
+    #[test]
+    fn sequential_update_matches_batch() {
+        let C = matrix![1., 0.; 0., 1.; 1., 1.];
+        let R_diag = matrix![0.5; 0.2; 1.];
+        let y = matrix![1.; -0.5; 0.3];
+
+        let mut filter = gravity_filter(0.1);
+        let mut sequential = gravity_filter(0.1);
+        filter.predict();
+        sequential.predict();
+
+        filter.update(&C, &SMatrix::from_diagonal(&R_diag), &y).unwrap();
+        sequential.update_sequential(&C, &R_diag, &y).unwrap();
+        assert!((filter.get_state() - sequential.get_state()).norm() < 1e-5);
+        assert!((filter.get_covariance() - sequential.get_covariance()).norm() < 1e-5);
+    }
+
+    #[test]
+    fn symmetrize_and_clamp_covariance() {
+        let P = matrix![2., 0.5; 0.3, 1.];
+        let mut filter = KalmanFilter::<2, 1, f32>::new(SMatrix::identity(), None, SMatrix::zeros(), matrix![0.; 0.], P);
+        filter.symmetrize();
+        assert_eq!(filter.get_covariance(), matrix![2., 0.4; 0.4, 1.]);
+
+        // An indefinite covariance gets its negative eigenvalue floored
+        let P = matrix![1., 2.; 2., 1.];
+        let mut filter = KalmanFilter::<2, 1, f32>::new(SMatrix::identity(), None, SMatrix::zeros(), matrix![0.; 0.], P);
+        filter.clamp_eigenvalues(1e-3);
+        let eigenvalues = filter.get_covariance().symmetric_eigenvalues();
+        assert_approx_eq!(eigenvalues.min(), 1e-3, 1e-5);
+        assert_approx_eq!(eigenvalues.max(), 3., 1e-5);
+    }
+
+    #[test]
+    fn steady_state_gain_matches_recursion() {
+        let td = 0.1;
+        let C = matrix![1., 0.];
+        let R = matrix![0.5];
+        let u = matrix![0.; -9.81 * td];
+        let mut filter = gravity_filter(td);
+        let K = filter.compute_steady_state_gain(&C, &R, 1e-5, 1000).unwrap();
+
+        // Gain of the recursive filter once converged
+        for _ in 0..200 {
+            filter.predict_with_input(u);
+            filter.update(&C, &R, &matrix![random::<f32>()]).unwrap();
+        }
+        filter.predict_with_input(u);
+        let P = filter.get_covariance();
+        let K_recursive = P * C.transpose() * (C * P * C.transpose() + R).try_inverse().unwrap();
+        assert!((K - K_recursive).norm() < 1e-4);
+
+        // At steady state the constant gain update matches the full update
+        let mut fixed = filter.clone();
+        filter.update(&C, &R, &matrix![0.5]).unwrap();
+        fixed.update_with_gain(&C, &matrix![0.5], &K);
+        assert!((filter.get_state() - fixed.get_state()).norm() < 1e-4);
+        assert!((filter.get_covariance() - fixed.get_covariance()).norm() < 1e-3);
+    }
+
+    #[test]
+    fn predicted_measurement() {
+        let x = matrix![2.; -1.];
+        let filter = KalmanFilter::<2, 1, f32>::new(SMatrix::identity(), None, SMatrix::zeros(), x, SMatrix::identity());
+        let C = matrix![1., 0.; 1., 2.; 0.5, 0.];
+        assert_eq!(filter.predict_measurement(&C), matrix![2.; 0.; 1.]);
+        assert_eq!(filter.measurement_residual(&C, &matrix![2.5; 0.; 1.]), matrix![0.5; 0.; 0.]);
+    }
+
+    #[test]
+    fn reset_estimate() {
+        let mut filter = gravity_filter(0.1);
+        for _ in 0..10 {
+            filter.predict();
+            filter.update(&matrix![1., 0.], &matrix![0.1], &matrix![3.]).unwrap();
+        }
+
+        let x_init = matrix![10.; -2.];
+        let P_init = matrix![4., 0.; 0., 9.];
+        filter.reset(x_init, P_init);
+        assert_eq!(filter.get_state(), x_init);
+        assert_eq!(filter.get_covariance(), P_init);
+
+        // The model is kept, and the next prediction starts from the new estimate
+        filter.predict();
+        assert_eq!(filter.get_state(), matrix![10. - 0.2; -2.]);
+    }
+
+    #[test]
+    fn clone_and_debug() {
+        let mut filter = gravity_filter(0.1);
+        filter.predict();
+        filter.update(&matrix![1., 0.], &matrix![0.1], &matrix![1.]).unwrap();
+
+        // Clones continue identically from the same mid-run estimate
+        let mut track = filter.clone();
+        for f in [&mut filter, &mut track] {
+            f.predict();
+            f.update(&matrix![1., 0.], &matrix![0.1], &matrix![1.5]).unwrap();
+        }
+        assert_eq!(track.get_state(), filter.get_state());
+
+        let debug = std::format!("{:?}", filter);
+        assert!(debug.starts_with("KalmanFilter {"));
+        assert!(debug.contains("A: ") && debug.contains("x: ") && debug.contains("P: "));
+    }
+
+    #[test]
+    fn state_bounds_hold_limit() {
+        // Battery state-of-charge charging at a constant rate
+        let mut filter = KalmanFilter::<1, 1, f32>::new(matrix![1.], Some(matrix![1.]), matrix![1e-4], matrix![0.9], matrix![0.01]);
+        filter.set_state_bounds(matrix![0.], matrix![1.]);
+
+        for _ in 0..10 {
+            filter.predict_with_input(matrix![0.05]);
+            assert!(filter.get_state()[0] <= 1.);
+        }
+        assert_eq!(filter.get_state()[0], 1.);
+
+        // A measurement beyond the bound is held at the limit too
+        filter.update(&matrix![1.], &matrix![0.01], &matrix![1.2]).unwrap();
+        assert_eq!(filter.get_state()[0], 1.);
+
+        filter.clear_state_bounds();
+        filter.update(&matrix![1.], &matrix![0.01], &matrix![1.2]).unwrap();
+        assert!(filter.get_state()[0] > 1.);
+    }
+
+    #[test]
+    fn two_updates_match_textbook_recursion() {
+        let A = matrix![1., 0.1; 0., 1.];
+        let Q = matrix![0.01, 0.; 0., 0.01];
+        let mut filter = KalmanFilter::<2, 1, f64>::new(A, None, Q, matrix![0.; 0.], matrix![1., 0.; 0., 1.]);
+        filter.predict();
+
+        // Textbook recursion: each update starts from the previous posterior
+        let mut x = matrix![0.; 0.];
+        let mut P = A * matrix![1., 0.; 0., 1.] * A.transpose() + Q;
+        let updates = [
+            (matrix![1., 0.], matrix![0.5], matrix![1.2]),
+            (matrix![0., 1.], matrix![0.1], matrix![-0.4]),
+        ];
+        for (C, R, y) in updates {
+            let S: SMatrix<f64, 1, 1> = C * P * C.transpose() + R;
+            let K = P * C.transpose() * S.try_inverse().unwrap();
+            x += K * (y - C * x);
+            P = (SMatrix::<f64, 2, 2>::identity() - K * C) * P;
+            filter.update(&C, &R, &y).unwrap();
+        }
+
+        assert!((filter.get_state() - x).norm() < 1e-12);
+        assert!((filter.cross_covariance(&[0, 1], &[0, 1]) - P).norm() < 1e-12);
+
+        // The next prediction propagates the final posterior
+        filter.predict();
+        let P = A * P * A.transpose() + Q;
+        assert!((filter.get_state() - A * x).norm() < 1e-12);
+        assert!((filter.cross_covariance(&[0, 1], &[0, 1]) - P).norm() < 1e-12);
+    }
+
+    #[test]
+    fn batch_update_matches_joint_update() {
+        let mut batch = gravity_filter(0.1);
+        batch.predict();
+        let (x, P) = (batch.get_state(), batch.cross_covariance(&[0, 1], &[0, 1]));
+
+        // Position sensor and a two-axis sensor observing velocity and the sum
+        let (C1, R1, y1) = (matrix![1., 0.], matrix![0.5], matrix![1.]);
+        let (C2, R2, y2) = (matrix![0., 1.; 1., 1.], matrix![0.2, 0.05; 0.05, 1.], matrix![-2.; 0.5]);
+        batch.update_batch::<1, 2, 3>((&C1, &R1, &y1), (&C2, &R2, &y2)).unwrap();
+
+        // Joint update written out in full
+        let C = matrix![1., 0.; 0., 1.; 1., 1.];
+        let R = matrix![0.5, 0., 0.; 0., 0.2, 0.05; 0., 0.05, 1.];
+        let y = matrix![1.; -2.; 0.5];
+        let K = P * C.transpose() * (C * P * C.transpose() + R).try_inverse().unwrap();
+        let x_joint = x + K * (y - C * x);
+        let P_joint = (SMatrix::<f32, 2, 2>::identity() - K * C) * P;
+
+        assert!((batch.get_state() - x_joint).norm() < 1e-5);
+        assert!((batch.cross_covariance(&[0, 1], &[0, 1]) - P_joint).norm() < 1e-5);
+    }
+
+    #[test]
+    fn kinematic_constructors() {
+        // Constant-velocity trajectory is propagated exactly
+        let mut cv = KalmanFilter::<2, 1, f64>::constant_velocity(0.5, 0.1);
+        cv.reset(matrix![1.; 2.], matrix![1., 0.; 0., 1.]);
+        for k in 1..=10 {
+            cv.predict();
+            assert_approx_eq!(cv.get_state()[0], 1. + 2. * 0.5 * k as f64, 1e-12);
+            assert_approx_eq!(cv.get_state()[1], 2., 1e-12);
+        }
+
+        // Constant-acceleration trajectory likewise
+        let mut ca = KalmanFilter::<3, 1, f64>::constant_acceleration(0.5, 0.1);
+        ca.reset(matrix![0.; 1.; -9.81], matrix![1., 0., 0.; 0., 1., 0.; 0., 0., 1.]);
+        for k in 1..=10 {
+            ca.predict();
+            let t = 0.5 * k as f64;
+            assert_approx_eq!(ca.get_state()[0], t - 9.81 * t * t / 2., 1e-9);
+            assert_approx_eq!(ca.get_state()[1], 1. - 9.81 * t, 1e-9);
+        }
+
+        // Position variance grows as dt^3/3 from the white-noise acceleration alone
+        let mut cv = KalmanFilter::<2, 1, f64>::constant_velocity(0.5, 0.1);
+        cv.reset(matrix![0.; 0.], matrix![0., 0.; 0., 0.]);
+        cv.predict();
+        assert_approx_eq!(cv.cross_covariance(&[0], &[0])[0], 0.1 * 0.125 / 3., 1e-12);
+
+        // Kinematic wrappers share the model of the constructors
+        let wrapper = ConstantVelocity::new(0.5, 0.1, matrix![0.; 0.], SMatrix::identity());
+        assert_eq!(wrapper.filter().get_A(), cv.get_A());
+        assert_eq!(wrapper.filter().get_Q(), cv.get_Q());
+        let wrapper = ConstantAcceleration::new(0.5, 0.1, matrix![0.; 0.; 0.], SMatrix::identity());
+        assert_eq!(wrapper.filter().get_A(), ca.get_A());
+        assert_eq!(wrapper.filter().get_Q(), ca.get_Q());
+    }
+
+    #[test]
+    fn van_loan_discretization() {
+        // Continuous white-noise acceleration model against its closed form
+        let (dt, q) = (0.5, 0.1);
+        let (A, Q) = kalman::discretize(&matrix![0., 1.; 0., 0.], &matrix![0., 0.; 0., q], dt);
+        assert!((A - matrix![1., dt; 0., 1.]).norm() < 1e-12);
+        let Q_exact = matrix![dt * dt * dt / 3., dt * dt / 2.; dt * dt / 2., dt] * q;
+        assert!((Q - Q_exact).norm() < 1e-12);
+
+        // First-order Gauss-Markov process, over a step long enough to need squaring
+        let (a, dt) = (0.8, 5.);
+        let (A, Q) = kalman::discretize(&matrix![-a], &matrix![q], dt);
+        assert_approx_eq!(A[0], f64::exp(-a * dt), 1e-12);
+        assert_approx_eq!(Q[0], q * (1. - f64::exp(-2. * a * dt)) / (2. * a), 1e-12);
+    }
+
+    #[test]
+    fn nan_components_are_skipped() {
+        let mut filter = gravity_filter(0.1);
+        filter.predict();
+        let I = matrix![1., 0.; 0., 1.];
+        let R = matrix![0.1, 0.; 0., 0.1];
+
+        // Only the position channel is observed
+        filter.update_masked(&I, &R, &matrix![2.; f32::NAN]).unwrap();
+        let mut expected = gravity_filter(0.1);
+        expected.predict();
+        expected.update(&matrix![1., 0.], &matrix![0.1], &matrix![2.]).unwrap();
+        assert!((filter.get_state() - expected.get_state()).norm() < 1e-6);
+        assert!(filter.get_state()[0] > 1.);
+
+        // A fully unavailable measurement is a no-op
+        let before = filter.get_state();
+        filter.update_masked(&I, &R, &matrix![f32::NAN; f32::NAN]).unwrap();
+        assert_eq!(filter.get_state(), before);
+    }
+
+    #[test]
+    fn adaptive_q_tracks_process_noise_jump() {
+        // Random walk whose process noise jumps from 0.01 to 1 halfway
+        let new_filter = || KalmanFilter::<1, 1, f32>::new(matrix![1.], None, matrix![0.01], matrix![0.], matrix![1.]);
+        let mut fixed = new_filter();
+        let mut adaptive = new_filter();
+        adaptive.enable_adaptive_q(20, 1000.);
+
+        let uniform = |var: f32| (random::<f32>() - 0.5) * (12. * var).sqrt();
+        let mut x = 0.;
+        let mut errors = (0., 0.);
+        for k in 0..2000 {
+            x += uniform(if k < 1000 { 0.01 } else { 1. });
+            let y = matrix![x + uniform(1.)];
+            for filter in [&mut fixed, &mut adaptive] {
+                filter.predict();
+                filter.update(&matrix![1.], &matrix![1.], &y).unwrap();
+            }
+            if k >= 1200 {
+                errors.0 += (fixed.get_state()[0] - x).powi(2);
+                errors.1 += (adaptive.get_state()[0] - x).powi(2);
+            }
+        }
+
+        assert!(errors.1 < 0.8 * errors.0);
+        assert!(adaptive.get_adaptive_q_scale() > 10.);
+    }
+
+    #[test]
+    fn mahalanobis_track_association() {
+        let mut filter = gravity_filter(0.1);
+        filter.predict();
+        let (C, R) = (matrix![1., 0.], matrix![0.5]);
+
+        // Predicted position variance is 2.01, so the innovation variance is 2.51
+        let near = filter.mahalanobis_distance(&C, &R, &matrix![0.1]).unwrap();
+        let far = filter.mahalanobis_distance(&C, &R, &matrix![10.]).unwrap();
+        assert_approx_eq!(near, 0.1 / 2.51f32.sqrt(), 1e-6);
+        assert_approx_eq!(far, 10. / 2.51f32.sqrt(), 1e-5);
+
+        // Singular innovation covariance
+        assert_eq!(filter.mahalanobis_distance(&matrix![0., 0.], &matrix![0.], &matrix![1.]), None);
+    }
+
+    #[test]
+    fn model_getters() {
+        let mut filter = gravity_filter(0.1);
+        assert_eq!(filter.get_A(), matrix![1., 0.1; 0., 1.]);
+
+        filter.set_A(matrix![1., 0.2; 0., 1.]);
+        filter.set_B(None);
+        filter.set_Q(matrix![0.5, 0.; 0., 0.5]);
+        assert_eq!(filter.get_A(), matrix![1., 0.2; 0., 1.]);
+        assert_eq!(filter.get_B(), matrix![0., 0.; 0., 0.]);
+        assert_eq!(filter.get_Q(), matrix![0.5, 0.; 0., 0.5]);
+    }
+
+    #[test]
+    fn equality_constraint_holds() {
+        // Three shares of a unit total, only the first of which is measured
+        let mut filter = KalmanFilter::<3, 1, f32>::new(
+            matrix![1., 0., 0.; 0., 1., 0.; 0., 0., 1.],
+            None,
+            matrix![0.01, 0., 0.; 0., 0.01, 0.; 0., 0., 0.01],
+            matrix![0.2; 0.3; 0.5],
+            matrix![1., 0., 0.; 0., 1., 0.; 0., 0., 1.],
+        );
+        filter.set_equality_constraint(&matrix![1., 1., 1.], &matrix![1.]);
+
+        for _ in 0..20 {
+            filter.predict();
+            filter.update(&matrix![1., 0., 0.], &matrix![0.01], &matrix![0.6 + 0.1 * (random::<f32>() - 0.5)]).unwrap();
+            assert_approx_eq!(filter.get_state().sum(), 1., 1e-5);
+        }
+        assert_approx_eq!(filter.get_state()[0], 0.6, 0.05);
+
+        // Redundant constraints are handled by the pseudo-inverse
+        filter.set_equality_constraint(&matrix![1., 1., 1.; 2., 2., 2.], &matrix![1.; 2.]);
+        filter.predict();
+        filter.update(&matrix![0., 1., 0.], &matrix![0.01], &matrix![0.3]).unwrap();
+        assert_approx_eq!(filter.get_state().sum(), 1., 1e-5);
+    }
+
+    #[test]
+    fn imm_tracks_maneuver() {
+        let dt = 0.1;
+        let cruise = || KalmanFilter::<2, 1, f64>::constant_velocity(dt, 1e-4);
+        let maneuver = || KalmanFilter::<2, 1, f64>::constant_velocity(dt, 10.);
+        let mut imm = ImmEstimator::new(
+            [cruise(), maneuver()],
+            matrix![0.98, 0.02; 0.05, 0.95],
+            matrix![0.5; 0.5],
+        );
+        let (mut cruise, mut maneuver) = (cruise(), maneuver());
+
+        // Cruise at 1 m/s, then reverse abruptly to -2 m/s
+        let (mut x, mut v) = (0., 1.);
+        let mut errors = (0., 0., 0.);
+        for k in 0..600 {
+            if (300..310).contains(&k) {
+                v -= 0.3;
+            }
+            x += v * dt;
+            let y = matrix![x + (random::<f64>() - 0.5)];
+            let (C, R) = (matrix![1., 0.], matrix![1. / 12.]);
+
+            imm.predict();
+            imm.update(&C, &R, &y).unwrap();
+            for filter in [&mut cruise, &mut maneuver] {
+                filter.predict();
+                filter.update(&C, &R, &y).unwrap();
+            }
+
+            if k >= 50 {
+                errors.0 += (imm.get_state()[0] - x).powi(2);
+                errors.1 += (cruise.get_state()[0] - x).powi(2);
+                errors.2 += (maneuver.get_state()[0] - x).powi(2);
+            }
+        }
+
+        assert!(errors.0 < errors.1);
+        assert!(errors.0 < errors.2);
+        assert_approx_eq!(imm.get_weights().sum(), 1., 1e-9);
+        assert!(imm.get_weights()[0] > 0.5);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn imm_update_is_atomic() {
+        // The second model rejects the measurement after the first has been updated
+        let mut strict = KalmanFilter::<2, 1, f64>::constant_velocity(0.1, 1.);
+        strict.set_debug_validation(Some(1.));
+        let mut imm = ImmEstimator::new(
+            [KalmanFilter::constant_velocity(0.1, 1e-4), strict],
+            matrix![0.98, 0.02; 0.05, 0.95],
+            matrix![0.5; 0.5],
+        );
+        imm.predict();
+        let (states, weights) = (imm.filters().each_ref().map(|filter| filter.get_state()), imm.get_weights());
+
+        let (C, R) = (matrix![1., 0.; 0., 1.], matrix![1., 0.; 0., 2.]);
+        assert_eq!(imm.update(&C, &R, &matrix![1.; 2.]), Err(KalmanError::IllConditionedInnovation));
+        assert_eq!(imm.filters().each_ref().map(|filter| filter.get_state()), states);
+        assert_eq!(imm.get_weights(), weights);
+    }
+
+    #[test]
+    fn covariance_summaries() {
+        let mut filter = gravity_filter(0.1);
+        assert_approx_eq!(filter.covariance_trace(), 2., 1e-6);
+        assert_approx_eq!(filter.covariance_determinant(), 1., 1e-6);
+
+        // Growing during prediction, with `det(A*P*A^T + Q)` for `P = I`
+        filter.predict();
+        assert_approx_eq!(filter.covariance_trace(), 4.01, 1e-5);
+        assert_approx_eq!(filter.covariance_determinant(), 2.01 * 2. - 0.1 * 0.1, 1e-5);
+
+        // Shrinking after an informative update
+        let (trace, determinant) = (filter.covariance_trace(), filter.covariance_determinant());
+        filter.update(&matrix![1., 0.], &matrix![0.1], &matrix![0.]).unwrap();
+        assert!(filter.covariance_trace() < trace);
+        assert!(filter.covariance_determinant() < determinant);
+
+        // Singular covariance
+        filter.reset(matrix![0.; 0.], matrix![1., 1.; 1., 1.]);
+        assert_approx_eq!(filter.covariance_determinant(), 0., 1e-6);
+    }
+
+    #[test]
+    fn correlated_noise_update() {
+        // Zero cross-covariance matches the standard update
+        let mut standard = gravity_filter(0.1);
+        let mut correlated = gravity_filter(0.1);
+        correlated.set_cross_covariance(&matrix![0.; 0.]);
+        for y in [1., 2., 2.5] {
+            standard.predict();
+            correlated.predict();
+            standard.update(&matrix![1., 0.], &matrix![0.5], &matrix![y]).unwrap();
+            correlated.update(&matrix![1., 0.], &matrix![0.5], &matrix![y]).unwrap();
+        }
+        assert!((standard.get_state() - correlated.get_state()).norm() < 1e-6);
+        assert!((standard.get_covariance() - correlated.get_covariance()).norm() < 1e-6);
+
+        // Random walk `x += w` measured as `y = x + a*w + e`, against a standard filter with
+        // the process noise `w` as an explicit state, `s = [x, w]`
+        let (q, a, r) = (0.5f64, 0.8, 0.2);
+        let mut scalar = KalmanFilter::<1, 1, f64>::new(matrix![1.], None, matrix![q], matrix![0.], matrix![1.]);
+        scalar.set_cross_covariance(&matrix![a * q]);
+        let mut joseph = scalar.clone();
+        let mut augmented = KalmanFilter::<2, 1, f64>::new(
+            matrix![1., 0.; 0., 0.],
+            None,
+            matrix![q, q; q, q],
+            matrix![0.; 0.],
+            matrix![1., 0.; 0., 0.],
+        );
+        for y in [0.3, -0.2, 0.9, 1.4] {
+            scalar.predict();
+            joseph.predict();
+            augmented.predict();
+            scalar.update(&matrix![1.], &matrix![a * a * q + r], &matrix![y]).unwrap();
+            joseph.update_joseph(&matrix![1.], &matrix![a * a * q + r], &matrix![y]).unwrap();
+            augmented.update(&matrix![1., a], &matrix![r], &matrix![y]).unwrap();
+            assert_approx_eq!(scalar.get_state()[0], augmented.get_state()[0], 1e-12);
+            assert_approx_eq!(scalar.get_covariance()[0], augmented.get_covariance()[(0, 0)], 1e-12);
+            assert_approx_eq!(joseph.get_state()[0], augmented.get_state()[0], 1e-12);
+            assert_approx_eq!(joseph.get_covariance()[0], augmented.get_covariance()[(0, 0)], 1e-12);
+        }
+
+        // Measurements of other dimensions are unaffected
+        let mut plain = gravity_filter(0.1);
+        let mut other = gravity_filter(0.1);
+        other.set_cross_covariance(&matrix![0.1, 0.; 0., 0.1]);
+        plain.predict();
+        other.predict();
+        plain.update(&matrix![1., 0.], &matrix![0.5], &matrix![1.]).unwrap();
+        other.update(&matrix![1., 0.], &matrix![0.5], &matrix![1.]).unwrap();
+        assert_eq!(plain.get_state(), other.get_state());
+    }
+
+    #[test]
+    fn correlated_noise_in_all_paths() {
+        let mut filter = KalmanFilter::<2, 1, f64>::constant_velocity(0.1, 1.);
+        filter.set_cross_covariance(&matrix![0.05, 0.; 0.02, 0.1]);
+        filter.set_nis_time_constant(1.);
+        filter.predict();
+        let (C, R, y) = (SMatrix::<f64, 2, 2>::identity(), matrix![0.5, 0.; 0., 0.3], matrix![0.8; -0.4]);
+
+        // The NIS of the query, the gate and the update agree
+        let nis = filter.normalized_innovation_squared(&C, &R, &y).unwrap();
+        let mut updated = filter.clone();
+        updated.update(&C, &R, &y).unwrap();
+        assert_approx_eq!(updated.smoothed_nis().unwrap(), nis, 1e-12);
+
+        let mut gated = filter.clone();
+        assert!(!gated.update_gated(&C, &R, &y, nis * 0.99));
+        assert!(gated.update_gated(&C, &R, &y, nis * 1.01));
+        assert_eq!(gated.get_state(), updated.get_state());
+        assert_approx_eq!(filter.mahalanobis_distance(&C, &R, &y).unwrap(), nis.sqrt(), 1e-12);
+
+        // The other updates and the hypothetical posterior match the update
+        let (x, P) = filter.hypothetical_posterior(&C, &R, &y).unwrap();
+        assert!((x - updated.get_state()).norm() < 1e-12);
+        assert!((P - updated.get_covariance()).norm() < 1e-12);
+
+        let mut sequential = filter.clone();
+        sequential.update_sequential(&C, &R.diagonal(), &y).unwrap();
+        assert!((sequential.get_state() - updated.get_state()).norm() < 1e-12);
+        assert!((sequential.get_covariance() - updated.get_covariance()).norm() < 1e-12);
+
+        let mut masked = filter.clone();
+        masked.update_with_state_mask(&C, &R, &y, &[true, true]).unwrap();
+        assert!((masked.get_state() - updated.get_state()).norm() < 1e-12);
+        assert!((masked.get_covariance() - updated.get_covariance()).norm() < 1e-12);
+    }
+
+    #[test]
+    fn stored_measurement_model() {
+        let (C, R) = (matrix![1., 0.], matrix![0.5]);
+        let mut sensor = SensorFilter::new(gravity_filter(0.1), C, R);
+        let mut explicit = gravity_filter(0.1);
+
+        for y in [0.5, 1.2, 2.1, 3.3] {
+            sensor.predict();
+            explicit.predict();
+            sensor.update_with(&matrix![y]).unwrap();
+            explicit.update(&C, &R, &matrix![y]).unwrap();
+            assert_eq!(sensor.get_state(), explicit.get_state());
+        }
+
+        // Other sensors are still fused with explicit models
+        sensor.update(&matrix![0., 1.], &matrix![0.1], &matrix![1.]).unwrap();
+        explicit.update(&matrix![0., 1.], &matrix![0.1], &matrix![1.]).unwrap();
+        assert_eq!(sensor.get_state(), explicit.get_state());
+        assert_eq!(sensor.filter().get_covariance(), explicit.get_covariance());
+    }
+
+    #[test]
+    fn rejected_measurement_count() {
+        let mut filter = gravity_filter(0.1);
+        let (C, R) = (matrix![1., 0.], matrix![0.1]);
+        for k in 0..50 {
+            filter.predict();
+            let y = if k % 10 == 5 { 100. } else { 0.1 * (random::<f32>() - 0.5) };
+            filter.update_gated(&C, &R, &matrix![y], 9.);
+        }
+        assert_eq!(filter.rejected_count(), 5);
+
+        // Singular innovation covariance
+        assert!(filter.update(&matrix![0., 0.], &matrix![0.], &matrix![1.]).is_err());
+        assert_eq!(filter.rejected_count(), 6);
+        assert!(filter.update_sequential(&matrix![0., 0.], &matrix![0.], &matrix![1.]).is_err());
+        assert!(filter.update_with_state_mask(&matrix![0., 0.], &matrix![0.], &matrix![1.], &[true, false]).is_err());
+        assert_eq!(filter.rejected_count(), 8);
+
+        // Singular components are counted once, whether rejected by the gate or the update
+        let thresholds = [1e3; 2];
+        assert_eq!(filter.update_gated_components(&matrix![0., 0.; 1., 0.], &matrix![0., 0.; 0., 0.1], &matrix![1.; 0.], &thresholds), [false, true]);
+        assert_eq!(filter.rejected_count(), 9);
+        assert_eq!(filter.update_gated_components(&matrix![1., 0.; 1., 0.], &SMatrix::zeros(), &matrix![0.; 0.], &thresholds), [false; 2]);
+        assert_eq!(filter.rejected_count(), 11);
+
+        filter.reset_rejected_count();
+        assert_eq!(filter.rejected_count(), 0);
+    }
+
+    #[test]
+    fn rotating_input_matrix() {
+        // Planar point mass with unit thrust rotating at unit rate, v = (sin t, 1 - cos t)
+        let dt = 1e-3;
+        let mut filter = KalmanFilter::<2, 1, f64>::new(
+            matrix![1., 0.; 0., 1.],
+            Some(matrix![0.; 0.]),
+            matrix![0., 0.; 0., 0.],
+            matrix![0.; 0.],
+            matrix![1., 0.; 0., 1.],
+        );
+        for k in 0..1000 {
+            let t = (k as f64 + 0.5) * dt;
+            filter.predict_with_input_and_matrix(matrix![1.], &matrix![t.cos() * dt; t.sin() * dt]);
+        }
+        assert_approx_eq!(filter.get_state()[0], 1f64.sin(), 1e-6);
+        assert_approx_eq!(filter.get_state()[1], 1. - 1f64.cos(), 1e-6);
+
+        // The stored input matrix is untouched
+        assert_eq!(filter.get_B(), matrix![0.; 0.]);
+    }
+
+    #[test]
+    fn huber_update_resists_outliers() {
+        let new_filter = || KalmanFilter::<1, 1, f32>::new(matrix![1.], None, matrix![0.01], matrix![0.], matrix![1.]);
+        let (mut plain, mut robust) = (new_filter(), new_filter());
+        let (mut huge_delta, mut tiny_delta) = (new_filter(), new_filter());
+        let (C, R) = (matrix![1.], matrix![1. / 12.]);
+
+        // Random walk measured with uniform noise, with one in ten measurements grossly wrong
+        let mut x = 0.;
+        let mut errors = (0., 0.);
+        for k in 0..500 {
+            x += 0.1 * (random::<f32>() - 0.5);
+            let outlier = if k % 10 == 0 { 50. * (random::<f32>() - 0.5) } else { 0. };
+            let y = matrix![x + random::<f32>() - 0.5 + outlier];
+
+            plain.predict();
+            robust.predict();
+            plain.update(&C, &R, &y).unwrap();
+            robust.update_robust(&C, &R, &y, 2.).unwrap();
+            errors.0 += (plain.get_state()[0] - x).powi(2);
+            errors.1 += (robust.get_state()[0] - x).powi(2);
+
+            // A huge delta matches the plain update, a tiny one hardly fuses at all
+            huge_delta.predict();
+            huge_delta.update_robust(&C, &R, &y, 1e9).unwrap();
+            assert_eq!(huge_delta.get_state(), plain.get_state());
+            tiny_delta.update_robust(&C, &R, &y, 1e-9).unwrap();
+        }
+
+        assert!(errors.1 < 0.5 * errors.0);
+        assert!(tiny_delta.get_covariance()[0] > 0.1);
+    }
+
+    #[test]
+    fn irregular_time_steps() {
+        // Constantly accelerated point mass over one second, at a fixed rate and irregularly
+        let F_cont = matrix![0., 1.; 0., 0.];
+        let B_cont = matrix![0.; 1.];
+        let Q_cont = matrix![0., 0.; 0., 0.1];
+        let new_filter = || {
+            let mut filter = KalmanFilter::<2, 1, f64>::zeros();
+            filter.set_continuous_model(F_cont, Some(B_cont), Q_cont);
+            filter
+        };
+        let (mut fixed, mut irregular) = (new_filter(), new_filter());
+
+        for _ in 0..10 {
+            fixed.predict_dt(0.1, matrix![2.]).unwrap();
+        }
+        for dt in [0.05, 0.3, 0.12, 0.18, 0.01, 0.34] {
+            irregular.predict_dt(dt, matrix![2.]).unwrap();
+        }
+
+        assert!((fixed.get_state() - matrix![1.; 2.]).norm() < 1e-12);
+        assert!((irregular.get_state() - fixed.get_state()).norm() < 1e-12);
+        assert!((irregular.get_covariance() - fixed.get_covariance()).norm() < 1e-12);
+        assert_approx_eq!(irregular.get_time(), 1., 1e-12);
+
+        // Non-positive steps are rejected
+        assert_eq!(irregular.predict_dt(0., matrix![2.]), Err(KalmanError::InvalidTimeStep));
+        assert_eq!(irregular.predict_dt(-0.1, matrix![2.]), Err(KalmanError::InvalidTimeStep));
+        assert_eq!(
+            KalmanFilter::<2, 1, f64>::zeros().predict_dt(0.1, matrix![2.]),
+            Err(KalmanError::NoContinuousModel),
+        );
+    }
+
+    #[test]
+    fn covariance_conditioning() {
+        let mut filter = KalmanFilter::<2, 2, f64>::new(
+            matrix![1., 0.1; 0., 1.],
+            None,
+            matrix![0., 0.; 0., 0.],
+            matrix![0.; 0.],
+            matrix![1., 0.; 0., 1.],
+        );
+        assert_approx_eq!(filter.covariance_condition_number(), 1., 1e-12);
+
+        // Ever more precise position measurements collapse the position variance
+        let mut condition = filter.covariance_condition_number();
+        for r in [1e-2, 1e-4, 1e-6, 1e-8] {
+            filter.update(&matrix![1., 0.], &matrix![r], &matrix![0.]).unwrap();
+            assert!(filter.covariance_condition_number() > 10. * condition);
+            assert!(filter.is_positive_definite());
+            condition = filter.covariance_condition_number();
+        }
+
+        // A transition discarding the velocity makes the covariance singular
+        filter.set_A(matrix![1., 0.1; 0., 0.]);
+        filter.predict();
+        assert!(!filter.is_positive_definite());
+        assert_eq!(filter.covariance_condition_number(), f64::INFINITY);
+    }
+
+    #[test]
+    fn replay_async_measurements() {
+        const G: f32 = 9.82;
+        let hz = 100;
+        let td = 1. / hz as f32;
+        let (C_pos, C_vel, R) = (matrix![1., 0.], matrix![0., 1.], matrix![1.]);
+        let u = matrix![0.5 * td.powf(2.0) * G; td * G];
+
+        // Record the asynchronous measurements of the hand-written loop
+        let mut filter = gravity_filter(td);
+        let mut steps = std::vec::Vec::new();
+        for i in 0..hz * 5 {
+            let s = i as f32 / hz as f32;
+            let pos = if i % 20 == 0 { Some(G * 0.5 * s.powf(2.0) + (random::<f32>() - 0.5)) } else { None };
+            let vel = if i % 5 == 0 { Some(G * s + (random::<f32>() - 0.5)) } else { None };
+
+            if let Some(pos) = pos {
+                filter.update(&C_pos, &R, &matrix![pos]).unwrap();
+                steps.push(FilterStep { u: None, measurement: Some((&C_pos, &R, matrix![pos])) });
+            }
+            if let Some(vel) = vel {
+                filter.update(&C_vel, &R, &matrix![vel]).unwrap();
+            }
+            filter.predict_with_input(u);
+            steps.push(FilterStep { u: Some(u), measurement: vel.map(|vel| (&C_vel, &R, matrix![vel])) });
+        }
+
+        let mut replayed = gravity_filter(td);
+        let states = replayed.run(&steps).unwrap();
+        assert_eq!(states.len(), steps.len());
+        assert_eq!(*states.last().unwrap(), filter.get_state());
+        assert_eq!(replayed.get_covariance(), filter.get_covariance());
+
+        // A step without measurement or input is a plain prediction
+        let mut plain = gravity_filter(td);
+        plain.predict();
+        let states = gravity_filter(td).run::<1>(&[FilterStep { u: None, measurement: None }]).unwrap();
+        assert_eq!(states[0], plain.get_state());
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let mut filter = gravity_filter(0.1);
+        filter.set_forgetting_factor(1.01);
+        filter.predict();
+        filter.update(&matrix![1., 0.], &matrix![0.5], &matrix![0.3]).unwrap();
+
+        // Mid-run, with a pending posterior
+        let json = serde_json::to_string(&filter).unwrap();
+        let mut loaded: KalmanFilter<2, 2, f32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.get_state(), filter.get_state());
+        assert_eq!(loaded.get_covariance(), filter.get_covariance());
+
+        // The next steps continue identically
+        let u = matrix![0.; -9.81 * 0.1];
+        filter.predict_with_input(u);
+        loaded.predict_with_input(u);
+        filter.update(&matrix![1., 0.], &matrix![0.5], &matrix![0.2]).unwrap();
+        loaded.update(&matrix![1., 0.], &matrix![0.5], &matrix![0.2]).unwrap();
+        assert_eq!(loaded.get_state(), filter.get_state());
+        assert_eq!(loaded.get_covariance(), filter.get_covariance());
+    }
 }